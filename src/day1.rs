@@ -1,9 +1,32 @@
-#![feature(array_windows)]
-
+use std::collections::VecDeque;
+use std::fmt::Write;
 use std::fs::read_to_string;
+use std::io::BufRead;
 use std::str::FromStr;
 
+// Run with no arguments to solve the puzzle, or
+//
+// * pass a window size and an optional gap to analyze readings streamed from stdin, e.g.
+//   `cat readings.txt | day1 3 1`
+// * pass `report` (or `report json`) to print statistics about the puzzle input
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if let Some(window) = args.first().filter(|arg| *arg != "report") {
+        let window = usize::from_str(window).expect("window is a number");
+        let gap = args
+            .get(1)
+            .map(|gap| usize::from_str(gap).expect("gap is a number"))
+            .unwrap_or(1);
+        let readings = std::io::stdin()
+            .lock()
+            .lines()
+            .map(|line| u64::from_str(line.expect("can read stdin").trim()).expect("valid number"));
+
+        println!("{:?}", analyze(readings, window, gap));
+        return;
+    }
+
     // Read the inputs
     let inputs: Vec<u64> = read_to_string("./src/inputs/day1.txt")
         .expect("can read file")
@@ -11,24 +34,393 @@ fn main() {
         .map(|line| u64::from_str(line).expect("valid number"))
         .collect();
 
+    if args.first().map(|arg| arg.as_str()) == Some("report") {
+        let report = Report::new(&inputs, REPORT_WINDOW);
+
+        match args.get(1).map(|arg| arg.as_str()) {
+            Some("json") => println!("{}", report.to_json()),
+            _ => print!("{}", report.to_table()),
+        }
+
+        return;
+    }
+
     // Part 1
-    let answer = inputs
-        .array_windows::<2>()
-        .filter(|[first, second]| second > first)
-        .count();
+    let answer = analyze(inputs.iter().cloned(), 1, 1).increased;
 
     println!("The answer to day 1 part 1 is: {}", answer);
 
     // Part 2
-    let sums: Vec<u64> = inputs
-        .array_windows::<3>()
-        .map(|[a, b, c]| a + b + c)
+    let answer = analyze(inputs.iter().cloned(), 3, 1).increased;
+
+    println!("The answer to day 1 part 2 is: {}", answer);
+}
+
+/// How window sums compare to the sum `gap` windows earlier
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Changes {
+    increased: usize,
+    decreased: usize,
+    flat: usize,
+}
+
+/// Compares sliding window sums as readings arrive, only holding on to the last `window`
+/// readings and the last `gap + 1` sums
+struct DepthAnalyzer {
+    window: usize,
+    gap: usize,
+    readings: VecDeque<u64>,
+    window_sum: u64,
+    sums: VecDeque<u64>,
+    changes: Changes,
+}
+
+impl DepthAnalyzer {
+    fn new(window: usize, gap: usize) -> Self {
+        assert!(window > 0, "window must hold at least one reading");
+        assert!(gap > 0, "gap must be at least one window");
+
+        DepthAnalyzer {
+            window,
+            gap,
+            readings: VecDeque::with_capacity(window + 1),
+            window_sum: 0,
+            sums: VecDeque::with_capacity(gap + 1),
+            changes: Changes::default(),
+        }
+    }
+
+    fn push(&mut self, reading: u64) {
+        use std::cmp::Ordering::*;
+
+        self.readings.push_back(reading);
+        self.window_sum += reading;
+
+        if self.readings.len() > self.window {
+            self.window_sum -= self.readings.pop_front().expect("not empty");
+        }
+
+        if self.readings.len() < self.window {
+            return;
+        }
+
+        self.sums.push_back(self.window_sum);
+
+        if self.sums.len() > self.gap {
+            let earlier = self.sums.pop_front().expect("not empty");
+
+            match self.window_sum.cmp(&earlier) {
+                Greater => self.changes.increased += 1,
+                Less => self.changes.decreased += 1,
+                Equal => self.changes.flat += 1,
+            }
+        }
+    }
+}
+
+fn analyze(readings: impl IntoIterator<Item = u64>, window: usize, gap: usize) -> Changes {
+    let mut analyzer = DepthAnalyzer::new(window, gap);
+
+    for reading in readings {
+        analyzer.push(reading);
+    }
+
+    analyzer.changes
+}
+
+/// Same size as the part 2 windows
+const REPORT_WINDOW: usize = 3;
+
+/// Readings whose modified z-score is above this are outliers, the usual cut off from Iglewicz
+/// and Hoaglin
+const OUTLIER_SCORE: f64 = 3.5;
+
+/// `length` readings in a row starting at `start`, each deeper than the one before
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Run {
+    start: usize,
+    length: usize,
+}
+
+/// The change from the reading at `index - 1` to the reading at `index`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Jump {
+    index: usize,
+    change: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Report {
+    readings: Vec<u64>,
+    window: usize,
+    longest_increasing_run: Run,
+    biggest_jump: Option<Jump>,
+    /// Trailing averages, the first one ends at reading `window - 1`
+    moving_averages: Vec<f64>,
+    /// Indexes of readings that stand out from their neighbors
+    outliers: Vec<usize>,
+}
+
+impl Report {
+    fn new(readings: &[u64], window: usize) -> Report {
+        Report {
+            readings: readings.to_vec(),
+            window,
+            longest_increasing_run: longest_increasing_run(readings),
+            biggest_jump: biggest_jump(readings),
+            moving_averages: moving_averages(readings, window),
+            outliers: outliers(readings, window),
+        }
+    }
+
+    fn to_table(&self) -> String {
+        let mut table = String::new();
+        let run = self.longest_increasing_run;
+
+        writeln!(table, "readings: {}", self.readings.len()).expect("can write");
+        writeln!(
+            table,
+            "longest increasing run: {} readings starting at index {}",
+            run.length, run.start
+        )
+        .expect("can write");
+        if let Some(jump) = self.biggest_jump {
+            writeln!(
+                table,
+                "biggest jump: {:+} at index {}",
+                jump.change, jump.index
+            )
+            .expect("can write");
+        }
+        writeln!(table, "outliers: {:?}", self.outliers).expect("can write");
+        writeln!(table).expect("can write");
+
+        writeln!(
+            table,
+            "{:>8} | {:>8} | {:>12} | outlier",
+            "index", "depth", "moving avg"
+        )
+        .expect("can write");
+        for (idx, reading) in self.readings.iter().enumerate() {
+            let average = match self.moving_average_at(idx) {
+                Some(average) => format!("{:.2}", average),
+                None => "-".to_string(),
+            };
+            let outlier = if self.outliers.contains(&idx) {
+                "yes"
+            } else {
+                "no"
+            };
+
+            writeln!(
+                table,
+                "{:>8} | {:>8} | {:>12} | {}",
+                idx, reading, average, outlier
+            )
+            .expect("can write");
+        }
+
+        table
+    }
+
+    fn to_json(&self) -> String {
+        let list = |items: Vec<String>| format!("[{}]", items.join(","));
+        let run = self.longest_increasing_run;
+        let jump = match self.biggest_jump {
+            Some(jump) => format!("{{\"index\":{},\"change\":{}}}", jump.index, jump.change),
+            None => "null".to_string(),
+        };
+
+        format!(
+            "{{\"readings\":{},\"window\":{},\"longest_increasing_run\":{{\"start\":{},\"length\":{}}},\"biggest_jump\":{},\"moving_averages\":{},\"outliers\":{}}}",
+            self.readings.len(),
+            self.window,
+            run.start,
+            run.length,
+            jump,
+            list(self.moving_averages.iter().map(|avg| format!("{:.2}", avg)).collect()),
+            list(self.outliers.iter().map(|idx| idx.to_string()).collect()),
+        )
+    }
+
+    fn moving_average_at(&self, idx: usize) -> Option<f64> {
+        let offset = (idx + 1).checked_sub(self.window)?;
+        self.moving_averages.get(offset).cloned()
+    }
+}
+
+fn longest_increasing_run(readings: &[u64]) -> Run {
+    let mut longest = Run {
+        start: 0,
+        length: readings.len().min(1),
+    };
+    let mut current = longest;
+
+    for idx in 1..readings.len() {
+        if readings[idx] > readings[idx - 1] {
+            current.length += 1;
+        } else {
+            current = Run {
+                start: idx,
+                length: 1,
+            };
+        }
+
+        if current.length > longest.length {
+            longest = current;
+        }
+    }
+
+    longest
+}
+
+/// The largest change between neighboring readings in either direction
+fn biggest_jump(readings: &[u64]) -> Option<Jump> {
+    (1..readings.len())
+        .map(|index| Jump {
+            index,
+            change: readings[index] as i64 - readings[index - 1] as i64,
+        })
+        .rev()
+        .max_by_key(|jump| jump.change.abs())
+}
+
+fn moving_averages(readings: &[u64], window: usize) -> Vec<f64> {
+    readings
+        .windows(window)
+        .map(|window| window.iter().sum::<u64>() as f64 / window.len() as f64)
+        .collect()
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.partial_cmp(b).expect("not NaN"));
+    let middle = values.len() / 2;
+
+    if values.len() % 2 == 1 {
+        values[middle]
+    } else {
+        (values[middle - 1] + values[middle]) / 2.0
+    }
+}
+
+/// Each reading is compared to the median of up to `window` readings on either side of it, the
+/// leftover residuals are then scored against their median absolute deviation. Medians keep a
+/// single spike from dragging its neighbors along with it.
+fn outliers(readings: &[u64], window: usize) -> Vec<usize> {
+    if readings.len() < 3 {
+        return vec![];
+    }
+
+    let residuals: Vec<f64> = (0..readings.len())
+        .map(|idx| {
+            let start = idx.saturating_sub(window);
+            let end = (idx + window + 1).min(readings.len());
+            let mut neighbors: Vec<f64> = (start..end)
+                .filter(|&other| other != idx)
+                .map(|other| readings[other] as f64)
+                .collect();
+
+            readings[idx] as f64 - median(&mut neighbors)
+        })
         .collect();
 
-    let answer = sums
-        .array_windows::<2>()
-        .filter(|[first, second]| second > first)
-        .count();
+    let center = median(&mut residuals.clone());
+    let mut deviations: Vec<f64> = residuals.iter().map(|r| (r - center).abs()).collect();
+    let mad = median(&mut deviations);
 
-    println!("The answer to day 1 part 2 is: {}", answer);
+    // With at least half the residuals identical the MAD is 0, so fall back to the mean absolute
+    // deviation, scaled to estimate the same spread
+    let spread = if mad > 0.0 {
+        mad / 0.6745
+    } else {
+        1.253314 * deviations.iter().sum::<f64>() / deviations.len() as f64
+    };
+
+    (0..readings.len())
+        .filter(|&idx| spread > 0.0 && (residuals[idx] - center).abs() / spread > OUTLIER_SCORE)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [u64; 10] = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
+    #[test]
+    fn example_from_advent_of_code() {
+        assert_eq!(
+            analyze(EXAMPLE, 1, 1),
+            Changes {
+                increased: 7,
+                decreased: 2,
+                flat: 0
+            }
+        );
+
+        assert_eq!(
+            analyze(EXAMPLE, 3, 1),
+            Changes {
+                increased: 5,
+                decreased: 1,
+                flat: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_gaps_and_short_inputs() {
+        // 199 -> 208, 200 -> 210, 208 -> 200, ...
+        assert_eq!(
+            analyze(EXAMPLE, 1, 2),
+            Changes {
+                increased: 5,
+                decreased: 3,
+                flat: 0
+            }
+        );
+
+        assert_eq!(analyze(EXAMPLE, 10, 1), Changes::default());
+        assert_eq!(analyze([], 3, 1), Changes::default());
+    }
+
+    #[test]
+    fn test_report() {
+        let report = Report::new(&EXAMPLE, 3);
+
+        assert_eq!(
+            report.longest_increasing_run,
+            Run {
+                start: 0,
+                length: 4
+            }
+        );
+        assert_eq!(
+            report.biggest_jump,
+            Some(Jump {
+                index: 6,
+                change: 33
+            })
+        );
+        assert_eq!(report.moving_averages.len(), 8);
+        assert_eq!(report.moving_average_at(1), None);
+        assert_eq!(report.moving_average_at(2), Some(607.0 / 3.0));
+        assert!(report.outliers.is_empty());
+
+        let json = report.to_json();
+        assert!(json.starts_with("{\"readings\":10,\"window\":3,"));
+        assert!(json.contains("\"biggest_jump\":{\"index\":6,\"change\":33}"));
+        assert!(json.ends_with("\"outliers\":[]}"));
+
+        assert_eq!(report.to_table().lines().count(), 5 + 1 + 10);
+    }
+
+    #[test]
+    fn test_outliers() {
+        let readings = [100, 101, 103, 102, 500, 104, 105, 107, 106, 108, 4, 110];
+        assert_eq!(outliers(&readings, 3), vec![4, 10]);
+
+        let flat = [7, 7, 7, 7, 9, 7, 7];
+        assert_eq!(outliers(&flat, 2), vec![4]);
+    }
 }
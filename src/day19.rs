@@ -0,0 +1,350 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+use std::str::FromStr;
+
+// Pass `export` to write the reconstructed map to ./output, as csv for a spreadsheet and as ply
+// for a 3D viewer
+fn main() {
+    let scanners = load_file("./src/inputs/day19.txt");
+    let placements = align(&scanners);
+    let beacons = beacon_map(&scanners, &placements);
+
+    match std::env::args().nth(1).as_deref() {
+        Some("export") => {
+            std::fs::create_dir_all("./output").expect("can create output dir");
+            std::fs::write("./output/day19-scanners.csv", scanners_csv(&placements))
+                .expect("can write scanners");
+            std::fs::write("./output/day19-beacons.csv", beacons_csv(&beacons))
+                .expect("can write beacons");
+            std::fs::write("./output/day19.ply", point_cloud_ply(&placements, &beacons))
+                .expect("can write point cloud");
+            return;
+        }
+        Some(other) => panic!("unknown argument {:?}", other),
+        None => {}
+    }
+
+    // Part 1
+    println!("Day 19 Pt 1 answer: {:?}", beacons.len());
+
+    // Part 2
+    println!(
+        "Day 19 Pt 2 answer: {:?}",
+        largest_distance_between_scanners(&placements)
+    );
+}
+
+type Point = (isize, isize, isize);
+
+type Rotation = [[isize; 3]; 3];
+
+const MIN_OVERLAPPING_BEACONS: usize = 12;
+
+#[derive(Debug)]
+struct Scanner {
+    id: usize,
+    beacons: Vec<Point>,
+}
+
+/// Where a scanner sits relative to scanner 0, and how to turn its readings into scanner 0's
+/// orientation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Placement {
+    position: Point,
+    rotation: Rotation,
+}
+
+impl Placement {
+    fn to_global(self, point: Point) -> Point {
+        add(self.position, rotate(&self.rotation, point))
+    }
+}
+
+/// All 24 proper rotations of a cube, built from the signed permutation matrices with a
+/// determinant of 1
+fn rotations() -> Vec<Rotation> {
+    let permutations = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+
+    permutations
+        .iter()
+        .flat_map(|perm| {
+            (0..8).map(move |signs: usize| {
+                let mut rotation = [[0; 3]; 3];
+
+                for (row, &col) in perm.iter().enumerate() {
+                    rotation[row][col] = if signs & (1 << row) == 0 { 1 } else { -1 };
+                }
+
+                rotation
+            })
+        })
+        .filter(|rotation| determinant(rotation) == 1)
+        .collect()
+}
+
+fn determinant(m: &Rotation) -> isize {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+fn rotate(rotation: &Rotation, (x, y, z): Point) -> Point {
+    let [a, b, c] = rotation.map(|row| row[0] * x + row[1] * y + row[2] * z);
+    (a, b, c)
+}
+
+fn add((x1, y1, z1): Point, (x2, y2, z2): Point) -> Point {
+    (x1 + x2, y1 + y2, z1 + z2)
+}
+
+fn sub((x1, y1, z1): Point, (x2, y2, z2): Point) -> Point {
+    (x1 - x2, y1 - y2, z1 - z2)
+}
+
+fn manhattan_distance(a: Point, b: Point) -> usize {
+    let (x, y, z) = sub(a, b);
+    (x.abs() + y.abs() + z.abs()) as usize
+}
+
+/// Try to line `unknown` up with beacons that are already in global coordinates
+///
+/// Every pairing of a known beacon with a rotated unknown beacon votes for an offset, an offset
+/// with enough votes is where the scanner must be
+fn find_placement(known: &[Point], unknown: &Scanner) -> Option<Placement> {
+    rotations().into_iter().find_map(|rotation| {
+        let mut votes: HashMap<Point, usize> = HashMap::new();
+
+        for &beacon in unknown.beacons.iter() {
+            let rotated = rotate(&rotation, beacon);
+
+            for &known_beacon in known.iter() {
+                *votes.entry(sub(known_beacon, rotated)).or_insert(0) += 1;
+            }
+        }
+
+        votes
+            .into_iter()
+            .find(|&(_offset, count)| count >= MIN_OVERLAPPING_BEACONS)
+            .map(|(position, _count)| Placement { position, rotation })
+    })
+}
+
+/// Place every scanner relative to scanner 0, the result is indexed by scanner id
+fn align(scanners: &[Scanner]) -> Vec<Placement> {
+    let identity = [[1, 0, 0], [0, 1, 0], [0, 0, 1]];
+
+    let mut placements: Vec<Option<Placement>> = vec![None; scanners.len()];
+    placements[0] = Some(Placement {
+        position: (0, 0, 0),
+        rotation: identity,
+    });
+
+    // Scanners that have been placed but not yet used to search for their neighbors
+    let mut frontier = vec![0];
+
+    while let Some(anchor) = frontier.pop() {
+        let anchor_placement = placements[anchor].expect("frontier scanners are placed");
+        let known: Vec<Point> = scanners[anchor]
+            .beacons
+            .iter()
+            .map(|&beacon| anchor_placement.to_global(beacon))
+            .collect();
+
+        for scanner in scanners.iter() {
+            if placements[scanner.id].is_some() {
+                continue;
+            }
+
+            if let Some(placement) = find_placement(&known, scanner) {
+                placements[scanner.id] = Some(placement);
+                frontier.push(scanner.id);
+            }
+        }
+    }
+
+    placements
+        .into_iter()
+        .map(|placement| placement.expect("every scanner overlaps another"))
+        .collect()
+}
+
+fn beacon_map(scanners: &[Scanner], placements: &[Placement]) -> BTreeSet<Point> {
+    scanners
+        .iter()
+        .flat_map(|scanner| {
+            let placement = placements[scanner.id];
+            scanner
+                .beacons
+                .iter()
+                .map(move |&beacon| placement.to_global(beacon))
+        })
+        .collect()
+}
+
+fn largest_distance_between_scanners(placements: &[Placement]) -> usize {
+    placements
+        .iter()
+        .flat_map(|a| {
+            placements
+                .iter()
+                .map(move |b| manhattan_distance(a.position, b.position))
+        })
+        .max()
+        .expect("at least one scanner")
+}
+
+/// One row per scanner with its position and the rotation matrix (row major) that takes its
+/// readings into scanner 0's orientation
+fn scanners_csv(placements: &[Placement]) -> String {
+    let mut csv = String::from("scanner,x,y,z,r00,r01,r02,r10,r11,r12,r20,r21,r22\n");
+
+    for (id, placement) in placements.iter().enumerate() {
+        let (x, y, z) = placement.position;
+        let rotation: Vec<String> = placement
+            .rotation
+            .iter()
+            .flatten()
+            .map(|n| n.to_string())
+            .collect();
+
+        writeln!(csv, "{},{},{},{},{}", id, x, y, z, rotation.join(",")).expect("can write");
+    }
+
+    csv
+}
+
+fn beacons_csv(beacons: &BTreeSet<Point>) -> String {
+    let mut csv = String::from("x,y,z\n");
+
+    for (x, y, z) in beacons.iter() {
+        writeln!(csv, "{},{},{}", x, y, z).expect("can write");
+    }
+
+    csv
+}
+
+/// An ascii PLY point cloud, scanners are colored red and beacons white
+fn point_cloud_ply(placements: &[Placement], beacons: &BTreeSet<Point>) -> String {
+    let mut ply = String::new();
+
+    writeln!(ply, "ply").expect("can write");
+    writeln!(ply, "format ascii 1.0").expect("can write");
+    writeln!(ply, "element vertex {}", placements.len() + beacons.len()).expect("can write");
+    for property in ["float x", "float y", "float z"] {
+        writeln!(ply, "property {}", property).expect("can write");
+    }
+    for property in ["uchar red", "uchar green", "uchar blue"] {
+        writeln!(ply, "property {}", property).expect("can write");
+    }
+    writeln!(ply, "end_header").expect("can write");
+
+    let scanners = placements
+        .iter()
+        .map(|placement| (placement.position, (255, 0, 0)));
+    let beacons = beacons.iter().map(|&beacon| (beacon, (255, 255, 255)));
+
+    for ((x, y, z), (r, g, b)) in scanners.chain(beacons) {
+        writeln!(ply, "{} {} {} {} {} {}", x, y, z, r, g, b).expect("can write");
+    }
+
+    ply
+}
+
+fn load_file(file_name: &str) -> Vec<Scanner> {
+    let file = std::fs::read_to_string(file_name).expect("file exists");
+    let mut scanners = Vec::new();
+
+    for line in file.lines().filter(|line| !line.is_empty()) {
+        if line.starts_with("--- scanner") {
+            let id = scanners.len();
+            scanners.push(Scanner {
+                id,
+                beacons: Vec::new(),
+            });
+        } else {
+            let mut parts = line
+                .split(",")
+                .map(|num| isize::from_str(num).expect("valid num"));
+            let x = parts.next().expect("has x");
+            let y = parts.next().expect("has y");
+            let z = parts.next().expect("has z");
+
+            scanners
+                .last_mut()
+                .expect("beacons follow a scanner header")
+                .beacons
+                .push((x, y, z));
+        }
+    }
+
+    scanners
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotations() {
+        let rotations = rotations();
+        assert_eq!(rotations.len(), 24);
+
+        let unique: BTreeSet<Point> = rotations
+            .iter()
+            .map(|rotation| rotate(rotation, (1, 2, 3)))
+            .collect();
+        assert_eq!(unique.len(), 24);
+    }
+
+    #[test]
+    fn example_from_advent_of_code() {
+        let scanners = load_file("./src/inputs/day19-example.txt");
+        let placements = align(&scanners);
+
+        let positions: Vec<Point> = placements.iter().map(|p| p.position).collect();
+        assert_eq!(
+            positions,
+            vec![
+                (0, 0, 0),
+                (68, -1246, -43),
+                (1105, -1205, 1229),
+                (-92, -2380, -20),
+                (-20, -1133, 1061)
+            ]
+        );
+
+        assert_eq!(beacon_map(&scanners, &placements).len(), 79);
+        assert_eq!(largest_distance_between_scanners(&placements), 3621);
+    }
+
+    #[test]
+    fn test_exports() {
+        let scanners = load_file("./src/inputs/day19-example.txt");
+        let placements = align(&scanners);
+        let beacons = beacon_map(&scanners, &placements);
+
+        let csv = scanners_csv(&placements);
+        let mut rows = csv.lines();
+        assert_eq!(
+            rows.next(),
+            Some("scanner,x,y,z,r00,r01,r02,r10,r11,r12,r20,r21,r22")
+        );
+        assert_eq!(rows.next(), Some("0,0,0,0,1,0,0,0,1,0,0,0,1"));
+        assert_eq!(rows.count(), 4);
+
+        // Scanner 1's readings land on the same beacons scanner 0 reported
+        assert!(beacons.contains(&placements[1].to_global((686, 422, 578))));
+        assert_eq!(beacons_csv(&beacons).lines().count(), 80);
+
+        let ply = point_cloud_ply(&placements, &beacons);
+        assert!(ply.contains("element vertex 84\n"));
+        assert_eq!(ply.lines().skip_while(|l| *l != "end_header").count(), 85);
+    }
+}
@@ -1,41 +1,293 @@
+use std::fmt::Write;
 use std::fs::read_to_string;
 use std::str::FromStr;
 
+// Pass `trajectory` to write both courses to ./output, as csv and as a plot, so the two sets of
+// rules can be compared
 fn main() {
     let file = read_to_string("./src/inputs/day2.txt").expect("can read file");
-    let inputs: Vec<(&str, u64)> = file
-        .lines()
-        .map(|line| line.split(" "))
-        .map(|mut words| {
-            let direction = words.next().expect("has a direction");
-            let val = words.next().expect("has a value");
-            (direction, u64::from_str(val).expect("valid number"))
-        })
-        .collect();
+    let commands = parse(&file).expect("valid commands");
+
+    match std::env::args().nth(1).as_deref() {
+        Some("trajectory") => {
+            std::fs::create_dir_all("./output").expect("can create output dir");
+            for (name, rules) in [("simple", simple as Rules), ("aimed", aimed as Rules)] {
+                let trajectory = Submarine::default().trajectory(&commands, rules);
+
+                std::fs::write(
+                    format!("./output/day2-{}.csv", name),
+                    trajectory_csv(&trajectory),
+                )
+                .expect("can write trajectory");
+                std::fs::write(
+                    format!("./output/day2-{}.txt", name),
+                    plot(&trajectory, 100, 40),
+                )
+                .expect("can write plot");
+            }
+            return;
+        }
+        Some(other) => panic!("unknown argument {:?}", other),
+        None => {}
+    }
 
     // Part 1
-    let (horizontal, depth): (u64, u64) = inputs.iter().fold(
-        (0, 0),
-        |(horizontal, depth), &(direction, value)| match direction {
-            "forward" => (horizontal + value, depth),
-            "up" => (horizontal, depth - value),
-            "down" => (horizontal, depth + value),
-            other => panic!("invalid direction, {:?}", other),
-        },
+    let submarine = Submarine::default().run(&commands, simple);
+    println!(
+        "day 2 part 1 answer: {:?}",
+        submarine.horizontal * submarine.depth
     );
 
-    println!("day 2 part 1 answer: {:?}", horizontal * depth);
-
     // Part 2
-    let (horizontal, depth, _aim): (u64, u64, u64) = inputs.iter().fold(
-        (0, 0, 0),
-        |(horizontal, depth, aim), &(direction, value)| match direction {
-            "forward" => (horizontal + value, depth + (aim * value), aim),
-            "up" => (horizontal, depth, aim - value),
-            "down" => (horizontal, depth, aim + value),
-            other => panic!("invalid direction, {:?}", other),
-        },
+    let submarine = Submarine::default().run(&commands, aimed);
+    println!(
+        "day 2 part 2 answer: {:?}",
+        submarine.horizontal * submarine.depth
     );
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Forward(i64),
+    Up(i64),
+    Down(i64),
+}
+
+use Command::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ParseCommandError {
+    UnknownDirection(String),
+    MissingValue,
+    InvalidValue(String),
+    /// Anything after the value, e.g. the `4` in `up 3 4`
+    TrailingWords(String),
+}
+
+impl FromStr for Command {
+    type Err = ParseCommandError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut words = line.split_whitespace();
+        let direction = words.next().unwrap_or_default();
+        let value = words.next().ok_or(ParseCommandError::MissingValue)?;
+        let value =
+            i64::from_str(value).map_err(|_| ParseCommandError::InvalidValue(value.to_string()))?;
+        let rest: Vec<&str> = words.collect();
+        if !rest.is_empty() {
+            return Err(ParseCommandError::TrailingWords(rest.join(" ")));
+        }
+
+        match direction {
+            "forward" => Ok(Forward(value)),
+            "up" => Ok(Up(value)),
+            "down" => Ok(Down(value)),
+            other => Err(ParseCommandError::UnknownDirection(other.to_string())),
+        }
+    }
+}
+
+/// Depth grows downwards, and can go negative if the submarine is told to fly
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Submarine {
+    horizontal: i64,
+    depth: i64,
+    aim: i64,
+}
+
+type Rules = fn(Submarine, Command) -> Submarine;
+
+impl Submarine {
+    fn run(self, commands: &[Command], rules: impl Fn(Submarine, Command) -> Submarine) -> Self {
+        commands
+            .iter()
+            .fold(self, |sub, &command| rules(sub, command))
+    }
+
+    /// Every state along the way, starting with `self` and ending with where `run` would stop
+    fn trajectory(
+        self,
+        commands: &[Command],
+        rules: impl Fn(Submarine, Command) -> Submarine,
+    ) -> Vec<Submarine> {
+        let mut states = vec![self];
+
+        for &command in commands {
+            let current = *states.last().expect("not empty");
+            states.push(rules(current, command));
+        }
+
+        states
+    }
+}
+
+fn trajectory_csv(trajectory: &[Submarine]) -> String {
+    let mut csv = String::from("step,horizontal,depth,aim\n");
+
+    for (step, sub) in trajectory.iter().enumerate() {
+        writeln!(csv, "{},{},{},{}", step, sub.horizontal, sub.depth, sub.aim).expect("can write");
+    }
+
+    csv
+}
+
+/// Scale the course into a `width` by `height` grid of characters, horizontal distance goes to
+/// the right and depth goes down the page
+fn plot(trajectory: &[Submarine], width: usize, height: usize) -> String {
+    let range = |values: Vec<i64>| {
+        let min = values.iter().cloned().min().unwrap_or(0);
+        let max = values.iter().cloned().max().unwrap_or(0);
+        (min, max)
+    };
+    let scale = |value: i64, (min, max): (i64, i64), cells: usize| {
+        let span = (max - min).max(1) as i128;
+        ((value - min) as i128 * (cells as i128 - 1) / span) as usize
+    };
+
+    let horizontal = range(trajectory.iter().map(|sub| sub.horizontal).collect());
+    let depth = range(trajectory.iter().map(|sub| sub.depth).collect());
+
+    let mut grid = vec![vec![' '; width]; height];
+    for sub in trajectory {
+        let col = scale(sub.horizontal, horizontal, width);
+        let row = scale(sub.depth, depth, height);
+        grid[row][col] = '*';
+    }
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "horizontal {}..{} (right), depth {}..{} (down)",
+        horizontal.0, horizontal.1, depth.0, depth.1
+    )
+    .expect("can write");
+
+    for row in grid {
+        let row: String = row.into_iter().collect();
+        writeln!(out, "|{}", row.trim_end()).expect("can write");
+    }
+    writeln!(out, "+{}", "-".repeat(width)).expect("can write");
+
+    out
+}
+
+/// Part 1 rules, up and down change the depth directly
+fn simple(sub: Submarine, command: Command) -> Submarine {
+    match command {
+        Forward(value) => Submarine {
+            horizontal: sub.horizontal + value,
+            ..sub
+        },
+        Up(value) => Submarine {
+            depth: sub.depth - value,
+            ..sub
+        },
+        Down(value) => Submarine {
+            depth: sub.depth + value,
+            ..sub
+        },
+    }
+}
+
+/// Part 2 rules, up and down change the aim and moving forward dives along it
+fn aimed(sub: Submarine, command: Command) -> Submarine {
+    match command {
+        Forward(value) => Submarine {
+            horizontal: sub.horizontal + value,
+            depth: sub.depth + sub.aim * value,
+            ..sub
+        },
+        Up(value) => Submarine {
+            aim: sub.aim - value,
+            ..sub
+        },
+        Down(value) => Submarine {
+            aim: sub.aim + value,
+            ..sub
+        },
+    }
+}
+
+fn parse(file: &str) -> Result<Vec<Command>, ParseCommandError> {
+    file.lines().map(Command::from_str).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "forward 5\ndown 5\nforward 8\nup 3\ndown 8\nforward 2";
+
+    #[test]
+    fn example_from_advent_of_code() {
+        let commands = parse(EXAMPLE).expect("valid commands");
+
+        let sub = Submarine::default().run(&commands, simple);
+        assert_eq!((sub.horizontal, sub.depth), (15, 10));
+
+        let sub = Submarine::default().run(&commands, aimed);
+        assert_eq!((sub.horizontal, sub.depth, sub.aim), (15, 60, 10));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Command::from_str("down 3"), Ok(Down(3)));
+        assert_eq!(
+            Command::from_str("backward 3"),
+            Err(ParseCommandError::UnknownDirection("backward".to_string()))
+        );
+        assert_eq!(
+            Command::from_str("up"),
+            Err(ParseCommandError::MissingValue)
+        );
+        assert_eq!(
+            Command::from_str("up lots"),
+            Err(ParseCommandError::InvalidValue("lots".to_string()))
+        );
+        assert_eq!(
+            Command::from_str("up 3 4"),
+            Err(ParseCommandError::TrailingWords("4".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_trajectory() {
+        let commands = parse(EXAMPLE).expect("valid commands");
+        let trajectory = Submarine::default().trajectory(&commands, simple);
+
+        let positions: Vec<(i64, i64)> = trajectory
+            .iter()
+            .map(|sub| (sub.horizontal, sub.depth))
+            .collect();
+        assert_eq!(
+            positions,
+            vec![(0, 0), (5, 0), (5, 5), (13, 5), (13, 2), (13, 10), (15, 10)]
+        );
+        assert_eq!(
+            trajectory.last(),
+            Some(&Submarine::default().run(&commands, simple))
+        );
+
+        let csv = trajectory_csv(&trajectory);
+        let mut rows = csv.lines();
+        assert_eq!(rows.next(), Some("step,horizontal,depth,aim"));
+        assert_eq!(rows.next(), Some("0,0,0,0"));
+        assert_eq!(rows.last(), Some("6,15,10,0"));
+
+        // One cell per unit in both directions
+        let plot = plot(&trajectory, 16, 11);
+        let rows: Vec<&str> = plot.lines().collect();
+        assert_eq!(rows[0], "horizontal 0..15 (right), depth 0..10 (down)");
+        assert_eq!(rows[1], "|*    *");
+        assert_eq!(rows[3], "|             *");
+        assert_eq!(rows[6], "|     *       *");
+        assert_eq!(rows[11], "|             * *");
+        assert_eq!(rows[12], "+----------------");
+    }
 
-    println!("day 2 part 2 answer: {:?}", horizontal * depth);
+    #[test]
+    fn test_going_above_the_surface() {
+        let sub = Submarine::default().run(&[Up(4), Forward(1)], simple);
+        assert_eq!(sub.depth, -4);
+    }
 }
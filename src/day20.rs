@@ -0,0 +1,302 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+// Pass `dump` to write every pass to ./output as text, pbm and pgm images so the region growth
+// and background flips can be inspected. It can be followed by how much background to draw
+// around the region, e.g. `day20 dump 5`
+fn main() {
+    let (algorithm, image) = load_file("./src/inputs/day20.txt");
+    let steps = image.enhancement_steps(&algorithm, 50);
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(|arg| arg.as_str()) == Some("dump") {
+        let padding = args
+            .get(1)
+            .map(|arg| usize::from_str(arg).expect("padding is a number"))
+            .unwrap_or(2);
+
+        std::fs::create_dir_all("./output").expect("can create output dir");
+        for (step, image) in steps.iter().enumerate() {
+            let path = format!("./output/day20-step-{:02}", step);
+            std::fs::write(format!("{}.txt", path), image.render(padding)).expect("can write text");
+            std::fs::write(format!("{}.pbm", path), image.to_pbm(padding)).expect("can write pbm");
+            std::fs::write(format!("{}.pgm", path), image.to_pgm(padding)).expect("can write pgm");
+        }
+        return;
+    }
+
+    // Part 1
+    println!(
+        "Day 20 Pt 1 answer: {:?}",
+        steps[2].lit_count().expect("background is dark")
+    );
+
+    // Part 2
+    println!(
+        "Day 20 Pt 2 answer: {:?}",
+        steps[50].lit_count().expect("background is dark")
+    );
+}
+
+type Position = (isize, isize);
+
+type Algorithm = [bool; 512];
+
+/// A finite region of pixels surrounded by an infinite background that is either all lit or all
+/// dark
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Image {
+    pixels: Vec<Vec<bool>>,
+    background: bool,
+}
+
+impl Image {
+    /// The original image followed by the image after each of the `times` passes
+    fn enhancement_steps(&self, algorithm: &Algorithm, times: usize) -> Vec<Image> {
+        let mut steps = vec![self.clone()];
+
+        for _ in 0..times {
+            let next = steps.last().expect("not empty").enhance(algorithm);
+            steps.push(next);
+        }
+
+        steps
+    }
+
+    /// Every output pixel depends on the 3x3 square around it, so the region grows by one pixel
+    /// on each side per pass. Everything further out only ever sees background, which means the
+    /// whole background flips when the algorithm maps an all dark square to a lit pixel.
+    fn enhance(&self, algorithm: &Algorithm) -> Image {
+        let pixels = (-1..(self.height() as isize + 1))
+            .map(|y| {
+                (-1..(self.width() as isize + 1))
+                    .map(|x| algorithm[self.index_at((x, y))])
+                    .collect()
+            })
+            .collect();
+
+        let background = if self.background {
+            algorithm[511]
+        } else {
+            algorithm[0]
+        };
+
+        Image { pixels, background }
+    }
+
+    fn index_at(&self, (x, y): Position) -> usize {
+        [
+            (x - 1, y - 1),
+            (x, y - 1),
+            (x + 1, y - 1),
+            (x - 1, y),
+            (x, y),
+            (x + 1, y),
+            (x - 1, y + 1),
+            (x, y + 1),
+            (x + 1, y + 1),
+        ]
+        .into_iter()
+        .fold(0, |index, pos| (index << 1) | self.at(pos) as usize)
+    }
+
+    fn at(&self, pos @ (x, y): Position) -> bool {
+        if self.is_in_region(pos) {
+            self.pixels[y as usize][x as usize]
+        } else {
+            self.background
+        }
+    }
+
+    /// `None` when the background is lit, since then infinitely many pixels are lit
+    fn lit_count(&self) -> Option<usize> {
+        if self.background {
+            return None;
+        }
+
+        Some(self.pixels.iter().flatten().filter(|&&lit| lit).count())
+    }
+
+    /// The positions to draw, the region plus `padding` pixels of background on every side
+    fn padded_rows(&self, padding: usize) -> impl Iterator<Item = Vec<Position>> {
+        let padding = padding as isize;
+        let width = self.width() as isize;
+        let height = self.height() as isize;
+
+        (-padding..(height + padding))
+            .map(move |y| (-padding..(width + padding)).map(|x| (x, y)).collect())
+    }
+
+    fn is_in_region(&self, (x, y): Position) -> bool {
+        0 <= x && x < (self.width() as isize) && 0 <= y && y < (self.height() as isize)
+    }
+
+    /// `#` for lit and `.` for dark, the same as the puzzle input
+    fn render(&self, padding: usize) -> String {
+        let mut out = String::new();
+
+        for row in self.padded_rows(padding) {
+            out.extend(
+                row.into_iter()
+                    .map(|pos| if self.at(pos) { '#' } else { '.' }),
+            );
+            out.push('\n');
+        }
+
+        out
+    }
+
+    /// Plain (ascii) PBM bitmap where lit pixels are black
+    fn to_pbm(&self, padding: usize) -> String {
+        let mut out = String::new();
+        let size = padding * 2;
+        writeln!(out, "P1\n{} {}", self.width() + size, self.height() + size).expect("can write");
+
+        for row in self.padded_rows(padding) {
+            let row: Vec<&str> = row
+                .into_iter()
+                .map(|pos| if self.at(pos) { "1" } else { "0" })
+                .collect();
+            writeln!(out, "{}", row.join(" ")).expect("can write");
+        }
+
+        out
+    }
+
+    /// Plain (ascii) PGM graymap where lit pixels are white, the padding is drawn in darker or
+    /// lighter gray than the region so the edge of the stored region stays visible
+    fn to_pgm(&self, padding: usize) -> String {
+        let mut out = String::new();
+        let size = padding * 2;
+        writeln!(
+            out,
+            "P2\n{} {}\n255",
+            self.width() + size,
+            self.height() + size
+        )
+        .expect("can write");
+
+        for row in self.padded_rows(padding) {
+            let row: Vec<&str> = row
+                .into_iter()
+                .map(|pos| match (self.at(pos), self.is_in_region(pos)) {
+                    (true, true) => "255",
+                    (false, true) => "0",
+                    (true, false) => "192",
+                    (false, false) => "64",
+                })
+                .collect();
+            writeln!(out, "{}", row.join(" ")).expect("can write");
+        }
+
+        out
+    }
+
+    fn width(&self) -> usize {
+        self.pixels.first().map(|row| row.len()).unwrap_or(0)
+    }
+
+    fn height(&self) -> usize {
+        self.pixels.len()
+    }
+}
+
+fn parse_pixel(c: char) -> bool {
+    match c {
+        '#' => true,
+        '.' => false,
+        other => panic!("invalid pixel {:?}", other),
+    }
+}
+
+fn load_file(file_name: &str) -> (Algorithm, Image) {
+    let file = std::fs::read_to_string(file_name).expect("file exists");
+    let mut lines = file.lines();
+
+    let algorithm: Vec<bool> = lines
+        .next()
+        .expect("has an algorithm")
+        .chars()
+        .map(parse_pixel)
+        .collect();
+    let algorithm: Algorithm = algorithm.try_into().expect("algorithm is 512 pixels");
+
+    let pixels = lines
+        .filter(|line| !line.is_empty())
+        .map(|line| line.chars().map(parse_pixel).collect())
+        .collect();
+
+    (
+        algorithm,
+        Image {
+            pixels,
+            background: false,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_from_advent_of_code() {
+        let (algorithm, image) = load_file("./src/inputs/day20-example.txt");
+
+        assert_eq!(image.index_at((2, 2)), 34);
+        assert_eq!(image.lit_count(), Some(10));
+        let steps = image.enhancement_steps(&algorithm, 50);
+        assert_eq!(steps[2].lit_count(), Some(35));
+        assert_eq!(steps[50].lit_count(), Some(3351));
+    }
+
+    #[test]
+    fn test_flipping_background() {
+        let (mut algorithm, image) = load_file("./src/inputs/day20-example.txt");
+        algorithm[0] = true;
+        algorithm[511] = false;
+
+        let once = image.enhance(&algorithm);
+        assert!(once.background);
+        assert_eq!(once.lit_count(), None);
+
+        let twice = once.enhance(&algorithm);
+        assert!(!twice.background);
+        assert!(twice.lit_count().is_some());
+    }
+
+    #[test]
+    fn test_rendering_steps() {
+        let (algorithm, image) = load_file("./src/inputs/day20-example.txt");
+        let steps = image.enhancement_steps(&algorithm, 2);
+        assert_eq!(steps.len(), 3);
+
+        assert_eq!(image.render(0), "#..#.\n#....\n##..#\n..#..\n..###\n");
+
+        // The example in the puzzle draws the first pass with 4 pixels of padding
+        let expected = [
+            "...............",
+            "...............",
+            "...............",
+            "...............",
+            ".....##.##.....",
+            "....#..#.#.....",
+            "....##.#..#....",
+            "....####..#....",
+            ".....#..##.....",
+            "......##..#....",
+            ".......#.#.....",
+            "...............",
+            "...............",
+            "...............",
+            "...............",
+        ];
+        assert_eq!(steps[1].render(4), expected.join("\n") + "\n");
+
+        let pbm = image.to_pbm(1);
+        assert!(pbm.starts_with("P1\n7 7\n0 0 0 0 0 0 0\n0 1 0 0 1 0 0\n"));
+
+        let pgm = image.to_pgm(1);
+        assert!(pgm.starts_with("P2\n7 7\n255\n64 64 64 64 64 64 64\n64 255 0 0 255 0 64\n"));
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+fn main() {
+    let starting = load_file("./src/inputs/day21.txt");
+
+    // Part 1
+    println!("Day 21 Pt 1 answer: {:?}", play_deterministic(starting));
+
+    // Part 2
+    let (player_1, player_2) = count_quantum_wins(starting);
+    println!("Day 21 Pt 2 answer: {:?}", player_1.max(player_2));
+}
+
+/// Positions are 1 through 10
+type Positions = (usize, usize);
+
+type Scores = (usize, usize);
+
+type Wins = (usize, usize);
+
+fn advance(position: usize, roll: usize) -> usize {
+    (position + roll - 1) % 10 + 1
+}
+
+/// Losing score times the number of rolls once someone reaches 1000
+fn play_deterministic((mut current, mut other): Positions) -> usize {
+    let mut die = (1..=100).cycle();
+    let mut rolls = 0;
+    let (mut current_score, mut other_score) = (0, 0);
+
+    loop {
+        let roll: usize = die.by_ref().take(3).sum();
+        rolls += 3;
+
+        current = advance(current, roll);
+        current_score += current;
+
+        if current_score >= 1000 {
+            return other_score * rolls;
+        }
+
+        std::mem::swap(&mut current, &mut other);
+        std::mem::swap(&mut current_score, &mut other_score);
+    }
+}
+
+/// How many ways three rolls of the Dirac die add up to each total
+fn dirac_roll_frequencies() -> HashMap<usize, usize> {
+    let mut frequencies = HashMap::new();
+
+    for a in 1..=3 {
+        for b in 1..=3 {
+            for c in 1..=3 {
+                *frequencies.entry(a + b + c).or_insert(0) += 1;
+            }
+        }
+    }
+
+    frequencies
+}
+
+/// The number of universes each player wins in, in the order the players were given
+fn count_quantum_wins(starting: Positions) -> Wins {
+    let frequencies = dirac_roll_frequencies();
+    let mut memoizations = HashMap::new();
+
+    count_wins(starting, (0, 0), &frequencies, &mut memoizations)
+}
+
+/// Wins for the player about to move and the other player from this state
+fn count_wins(
+    (current, other): Positions,
+    (current_score, other_score): Scores,
+    frequencies: &HashMap<usize, usize>,
+    memoizations: &mut HashMap<(Positions, Scores), Wins>,
+) -> Wins {
+    let key = ((current, other), (current_score, other_score));
+
+    if let Some(wins) = memoizations.get(&key) {
+        return *wins;
+    }
+
+    let mut wins = (0, 0);
+
+    for (&roll, &universes) in frequencies.iter() {
+        let next = advance(current, roll);
+        let score = current_score + next;
+
+        if score >= 21 {
+            wins.0 += universes;
+        } else {
+            // Swap who's moving, so their wins come back flipped
+            let (other_wins, current_wins) = count_wins(
+                (other, next),
+                (other_score, score),
+                frequencies,
+                memoizations,
+            );
+            wins.0 += current_wins * universes;
+            wins.1 += other_wins * universes;
+        }
+    }
+
+    memoizations.insert(key, wins);
+    wins
+}
+
+fn load_file(file_name: &str) -> Positions {
+    let file = std::fs::read_to_string(file_name).expect("file exists");
+    let mut positions = file.lines().map(|line| {
+        let (_player, position) = line.split_once(": ").expect("has a starting position");
+        usize::from_str(position).expect("valid num")
+    });
+
+    let player_1 = positions.next().expect("has player 1");
+    let player_2 = positions.next().expect("has player 2");
+
+    (player_1, player_2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_from_advent_of_code() {
+        let starting = load_file("./src/inputs/day21-example.txt");
+        assert_eq!(starting, (4, 8));
+
+        assert_eq!(play_deterministic(starting), 739785);
+        assert_eq!(
+            count_quantum_wins(starting),
+            (444356092776315, 341960390180808)
+        );
+    }
+
+    #[test]
+    fn test_advance_wraps_around() {
+        assert_eq!(advance(7, 5), 2);
+        assert_eq!(advance(10, 10), 10);
+        assert_eq!(advance(1, 9), 10);
+    }
+}
@@ -0,0 +1,211 @@
+use std::str::FromStr;
+
+fn main() {
+    let steps = load_file("./src/inputs/day22.txt");
+
+    // Part 1
+    let initialization_region = Cuboid {
+        x: (-50, 50),
+        y: (-50, 50),
+        z: (-50, 50),
+    };
+    println!(
+        "Day 22 Pt 1 answer: {:?}",
+        count_on(&steps, Some(initialization_region))
+    );
+
+    // Part 2
+    println!("Day 22 Pt 2 answer: {:?}", count_on(&steps, None));
+}
+
+/// Inclusive on both ends
+type Range = (isize, isize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cuboid {
+    x: Range,
+    y: Range,
+    z: Range,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Step {
+    on: bool,
+    cuboid: Cuboid,
+}
+
+impl Cuboid {
+    fn intersection(&self, other: &Cuboid) -> Option<Cuboid> {
+        let overlap = |(a_start, a_end): Range, (b_start, b_end): Range| {
+            let start = a_start.max(b_start);
+            let end = a_end.min(b_end);
+            (start <= end).then_some((start, end))
+        };
+
+        Some(Cuboid {
+            x: overlap(self.x, other.x)?,
+            y: overlap(self.y, other.y)?,
+            z: overlap(self.z, other.z)?,
+        })
+    }
+
+    fn volume(&self) -> isize {
+        [self.x, self.y, self.z]
+            .iter()
+            .map(|(start, end)| end - start + 1)
+            .product()
+    }
+}
+
+/// Keeps a list of signed cuboids, every new cuboid cancels out its overlap with everything
+/// before it, and "on" cuboids are then added themselves. The signed volumes sum to the number of
+/// cubes that are on without ever visiting a single cube.
+fn count_on(steps: &[Step], bounds: Option<Cuboid>) -> usize {
+    let mut signed: Vec<(Cuboid, isize)> = Vec::new();
+
+    for step in steps {
+        let cuboid = match bounds {
+            Some(bounds) => match step.cuboid.intersection(&bounds) {
+                Some(cuboid) => cuboid,
+                None => continue,
+            },
+            None => step.cuboid,
+        };
+
+        let cancellations: Vec<(Cuboid, isize)> = signed
+            .iter()
+            .filter_map(|(existing, sign)| {
+                existing
+                    .intersection(&cuboid)
+                    .map(|overlap| (overlap, -sign))
+            })
+            .collect();
+
+        signed.extend(cancellations);
+
+        if step.on {
+            signed.push((cuboid, 1));
+        }
+    }
+
+    signed
+        .iter()
+        .map(|(cuboid, sign)| cuboid.volume() * sign)
+        .sum::<isize>() as usize
+}
+
+fn parse_range(range: &str) -> Range {
+    let (_axis, range) = range.split_once("=").expect("has an axis");
+    let (start, end) = range.split_once("..").expect("is a range");
+
+    (
+        isize::from_str(start).expect("valid num"),
+        isize::from_str(end).expect("valid num"),
+    )
+}
+
+fn load_file(file_name: &str) -> Vec<Step> {
+    std::fs::read_to_string(file_name)
+        .expect("file exists")
+        .lines()
+        .map(|line| {
+            let (state, ranges) = line.split_once(" ").expect("has a state");
+            let on = match state {
+                "on" => true,
+                "off" => false,
+                other => panic!("invalid state {:?}", other),
+            };
+
+            let mut ranges = ranges.split(",").map(parse_range);
+            let x = ranges.next().expect("has x");
+            let y = ranges.next().expect("has y");
+            let z = ranges.next().expect("has z");
+
+            Step {
+                on,
+                cuboid: Cuboid { x, y, z },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_from_advent_of_code() {
+        let steps = load_file("./src/inputs/day22-example.txt");
+
+        // Cubes that are on after each step
+        let expected = [27, 27 + 19, 27 + 19 - 8, 39];
+        for (count, expected) in (1..=steps.len()).zip(expected) {
+            assert_eq!(count_on(&steps[..count], None), expected);
+        }
+    }
+
+    #[test]
+    fn reboot_example_from_advent_of_code() {
+        let initialization_region = Cuboid {
+            x: (-50, 50),
+            y: (-50, 50),
+            z: (-50, 50),
+        };
+
+        let steps = load_file("./src/inputs/day22-example-reboot.txt");
+        assert_eq!(count_on(&steps, Some(initialization_region)), 474140);
+        assert_eq!(count_on(&steps, None), 2758514936282235);
+    }
+
+    fn count_on_by_visiting_every_cube(steps: &[Step], bounds: Cuboid) -> usize {
+        let mut count = 0;
+
+        for x in bounds.x.0..=bounds.x.1 {
+            for y in bounds.y.0..=bounds.y.1 {
+                for z in bounds.z.0..=bounds.z.1 {
+                    let contains = |c: &Cuboid| {
+                        c.x.0 <= x
+                            && x <= c.x.1
+                            && c.y.0 <= y
+                            && y <= c.y.1
+                            && c.z.0 <= z
+                            && z <= c.z.1
+                    };
+
+                    let last = steps.iter().rev().find(|step| contains(&step.cuboid));
+                    if matches!(last, Some(Step { on: true, .. })) {
+                        count += 1;
+                    }
+                }
+            }
+        }
+
+        count
+    }
+
+    #[test]
+    fn test_bounds() {
+        let steps = load_file("./src/inputs/day22-example.txt");
+        let bounds = Cuboid {
+            x: (11, 50),
+            y: (9, 12),
+            z: (0, 20),
+        };
+
+        assert_eq!(
+            count_on(&steps, Some(bounds)),
+            count_on_by_visiting_every_cube(&steps, bounds)
+        );
+
+        let far_away = Step {
+            on: true,
+            cuboid: Cuboid {
+                x: (100, 200),
+                y: (0, 0),
+                z: (0, 0),
+            },
+        };
+        assert_eq!(count_on(&[far_away], Some(bounds)), 0);
+        assert_eq!(count_on(&[far_away], None), 101);
+    }
+}
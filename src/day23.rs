@@ -0,0 +1,209 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+fn main() {
+    let burrow = load_file("./src/inputs/day23.txt");
+
+    // Part 1
+    println!("Day 23 Pt 1 answer: {:?}", least_energy(&burrow));
+
+    // Part 2
+    println!("Day 23 Pt 2 answer: {:?}", least_energy(&burrow.unfold()));
+}
+
+/// 0 is an empty space, 1 through 4 are Amber, Bronze, Copper and Desert
+type Amphipod = u8;
+
+const EMPTY: Amphipod = 0;
+
+/// Hallway spots directly outside each room, amphipods never stop on these
+const ROOM_ENTRANCES: [usize; 4] = [2, 4, 6, 8];
+
+const ENERGY_PER_STEP: [usize; 4] = [1, 10, 100, 1000];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Burrow {
+    hallway: [Amphipod; 11],
+    /// Index 0 of each room is the space next to the hallway
+    rooms: [Vec<Amphipod>; 4],
+}
+
+impl Burrow {
+    fn is_organized(&self) -> bool {
+        self.rooms
+            .iter()
+            .enumerate()
+            .all(|(room, occupants)| occupants.iter().all(|&a| a == home_of(room)))
+    }
+
+    /// The rows the puzzle folded out of the diagram for part 2
+    fn unfold(&self) -> Burrow {
+        let hidden = [[4, 4], [3, 2], [2, 1], [1, 3]];
+        let mut unfolded = self.clone();
+
+        for (room, hidden) in unfolded.rooms.iter_mut().zip(hidden) {
+            room.splice(1..1, hidden);
+        }
+
+        unfolded
+    }
+
+    fn is_hallway_clear(&self, from: usize, to: usize) -> bool {
+        let (start, end) = (from.min(to), from.max(to));
+        self.hallway[start..=end].iter().all(|&spot| spot == EMPTY)
+    }
+
+    /// A room only accepts amphipods once everyone who doesn't belong in it has left
+    fn is_accepting(&self, room: usize) -> bool {
+        self.rooms[room]
+            .iter()
+            .all(|&a| a == EMPTY || a == home_of(room))
+    }
+
+    /// Every legal move with the energy it costs. Amphipods either leave their room for a spot in
+    /// the hallway or go from the hallway all the way into their own room, a move from room to
+    /// room is the same as one of each.
+    fn moves(&self) -> Vec<(usize, Burrow)> {
+        let mut moves = Vec::new();
+
+        for (spot, &amphipod) in self.hallway.iter().enumerate() {
+            if amphipod == EMPTY {
+                continue;
+            }
+
+            let room = (amphipod - 1) as usize;
+            let entrance = ROOM_ENTRANCES[room];
+            let next_to_entrance = if spot < entrance { spot + 1 } else { spot - 1 };
+
+            if !self.is_accepting(room) || !self.is_hallway_clear(next_to_entrance, entrance) {
+                continue;
+            }
+
+            let depth = self.rooms[room]
+                .iter()
+                .rposition(|&a| a == EMPTY)
+                .expect("accepting rooms have space");
+
+            let mut next = self.clone();
+            next.hallway[spot] = EMPTY;
+            next.rooms[room][depth] = amphipod;
+
+            let steps = spot.abs_diff(entrance) + depth + 1;
+            moves.push((steps * ENERGY_PER_STEP[room], next));
+        }
+
+        for (room, occupants) in self.rooms.iter().enumerate() {
+            if self.is_accepting(room) {
+                continue;
+            }
+
+            let depth = occupants
+                .iter()
+                .position(|&a| a != EMPTY)
+                .expect("rooms that aren't accepting have someone to move");
+            let amphipod = occupants[depth];
+            let entrance = ROOM_ENTRANCES[room];
+
+            for spot in (0..self.hallway.len()).filter(|spot| !ROOM_ENTRANCES.contains(spot)) {
+                if !self.is_hallway_clear(entrance, spot) {
+                    continue;
+                }
+
+                let mut next = self.clone();
+                next.rooms[room][depth] = EMPTY;
+                next.hallway[spot] = amphipod;
+
+                let steps = depth + 1 + entrance.abs_diff(spot);
+                moves.push((steps * ENERGY_PER_STEP[(amphipod - 1) as usize], next));
+            }
+        }
+
+        moves
+    }
+}
+
+fn home_of(room: usize) -> Amphipod {
+    room as Amphipod + 1
+}
+
+/// Dijkstra's over burrow states
+fn least_energy(start: &Burrow) -> usize {
+    let mut best: HashMap<Burrow, usize> = HashMap::from_iter([(start.clone(), 0)]);
+    let mut queue = BinaryHeap::from_iter([Reverse((0, start.clone()))]);
+
+    while let Some(Reverse((energy, burrow))) = queue.pop() {
+        if burrow.is_organized() {
+            return energy;
+        }
+
+        if matches!(best.get(&burrow), Some(&known) if known < energy) {
+            continue;
+        }
+
+        for (cost, next) in burrow.moves() {
+            let next_energy = energy + cost;
+
+            if !matches!(best.get(&next), Some(&known) if known <= next_energy) {
+                best.insert(next.clone(), next_energy);
+                queue.push(Reverse((next_energy, next)));
+            }
+        }
+    }
+
+    panic!("the amphipods can't be organized")
+}
+
+fn load_file(file_name: &str) -> Burrow {
+    let file = std::fs::read_to_string(file_name).expect("file exists");
+    let mut rooms: [Vec<Amphipod>; 4] = Default::default();
+
+    for line in file.lines().skip(2) {
+        let row: Vec<char> = line.chars().collect();
+
+        for (room, &column) in rooms.iter_mut().zip(&[3, 5, 7, 9]) {
+            match row.get(column) {
+                Some(&c @ 'A'..='D') => room.push(c as Amphipod - b'A' + 1),
+                Some('#') | None => {}
+                other => panic!("invalid room space {:?}", other),
+            }
+        }
+    }
+
+    Burrow {
+        hallway: [EMPTY; 11],
+        rooms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_file() {
+        let burrow = load_file("./src/inputs/day23-example.txt");
+        assert_eq!(
+            burrow.rooms,
+            [vec![2, 1], vec![3, 4], vec![2, 3], vec![4, 1]]
+        );
+
+        let unfolded = burrow.unfold();
+        assert_eq!(
+            unfolded.rooms,
+            [
+                vec![2, 4, 4, 1],
+                vec![3, 3, 2, 4],
+                vec![2, 2, 1, 3],
+                vec![4, 1, 3, 1]
+            ]
+        );
+    }
+
+    #[test]
+    fn example_from_advent_of_code() {
+        let burrow = load_file("./src/inputs/day23-example.txt");
+
+        assert_eq!(least_energy(&burrow), 12521);
+        assert_eq!(least_energy(&burrow.unfold()), 44169);
+    }
+}
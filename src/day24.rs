@@ -0,0 +1,244 @@
+use std::str::FromStr;
+
+fn main() {
+    let file = std::fs::read_to_string("./src/inputs/day24.txt").expect("file exists");
+    let program = parse(&file);
+    let blocks = monad_blocks(&program);
+
+    // Part 1
+    let largest = model_number(&blocks, true);
+    assert_eq!(
+        run(&program, &largest)[Z],
+        0,
+        "MONAD accepts the model number"
+    );
+    println!("Day 24 Pt 1 answer: {:?}", digits_to_number(&largest));
+
+    // Part 2
+    let smallest = model_number(&blocks, false);
+    assert_eq!(
+        run(&program, &smallest)[Z],
+        0,
+        "MONAD accepts the model number"
+    );
+    println!("Day 24 Pt 2 answer: {:?}", digits_to_number(&smallest));
+}
+
+type Register = usize;
+
+const W: Register = 0;
+const X: Register = 1;
+const Y: Register = 2;
+const Z: Register = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Register(Register),
+    Value(isize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Instruction {
+    Inp(Register),
+    Add(Register, Operand),
+    Mul(Register, Operand),
+    Div(Register, Operand),
+    Mod(Register, Operand),
+    Eql(Register, Operand),
+}
+
+use Instruction::*;
+
+/// Run a program to completion and return the final `[w, x, y, z]`
+fn run(program: &[Instruction], inputs: &[isize]) -> [isize; 4] {
+    let mut registers = [0; 4];
+    let mut inputs = inputs.iter();
+
+    for instruction in program {
+        let value = |operand: Operand| match operand {
+            Operand::Register(r) => registers[r],
+            Operand::Value(v) => v,
+        };
+
+        match *instruction {
+            Inp(a) => registers[a] = *inputs.next().expect("enough inputs"),
+            Add(a, b) => registers[a] += value(b),
+            Mul(a, b) => registers[a] *= value(b),
+            Div(a, b) => registers[a] /= value(b),
+            Mod(a, b) => registers[a] %= value(b),
+            Eql(a, b) => registers[a] = (registers[a] == value(b)) as isize,
+        }
+    }
+
+    registers
+}
+
+/// MONAD is 14 copies of the same 18 instruction block that only differ in three constants.
+/// Each block treats `z` as a stack of base 26 digits and either pushes `w + offset`, or pops
+/// and only avoids pushing again if the popped value plus `check` equals `w`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Block {
+    pops: bool,
+    check: isize,
+    offset: isize,
+}
+
+fn monad_blocks(program: &[Instruction]) -> Vec<Block> {
+    program
+        .chunks(18)
+        .map(|block| match (block[0], block[4], block[5], block[15]) {
+            (
+                Inp(W),
+                Div(Z, Operand::Value(divisor)),
+                Add(X, Operand::Value(check)),
+                Add(Y, Operand::Value(offset)),
+            ) => Block {
+                pops: divisor == 26,
+                check,
+                offset,
+            },
+            other => panic!("block doesn't look like MONAD {:?}", other),
+        })
+        .collect()
+}
+
+/// Every popping block is paired with the pushing block whose value it pops, and the pair
+/// constrains their digits to `popped = pushed + offset + check`. Each pair is solved
+/// independently by picking the largest (or smallest) digits that fit in 1 through 9.
+fn model_number(blocks: &[Block], largest: bool) -> Vec<isize> {
+    let mut digits = vec![0; blocks.len()];
+    let mut pushed: Vec<(usize, isize)> = Vec::new();
+
+    for (idx, block) in blocks.iter().enumerate() {
+        if !block.pops {
+            pushed.push((idx, block.offset));
+            continue;
+        }
+
+        let (pushed_idx, offset) = pushed.pop().expect("pops match a push");
+        let diff = offset + block.check;
+
+        let pushed_digit = if largest {
+            9.min(9 - diff)
+        } else {
+            1.max(1 - diff)
+        };
+
+        digits[pushed_idx] = pushed_digit;
+        digits[idx] = pushed_digit + diff;
+    }
+
+    assert!(pushed.is_empty(), "every push is popped");
+    digits
+}
+
+fn digits_to_number(digits: &[isize]) -> u64 {
+    digits
+        .iter()
+        .fold(0, |number, &digit| number * 10 + digit as u64)
+}
+
+fn parse_register(register: &str) -> Register {
+    match register {
+        "w" => W,
+        "x" => X,
+        "y" => Y,
+        "z" => Z,
+        other => panic!("invalid register {:?}", other),
+    }
+}
+
+fn parse_operand(operand: &str) -> Operand {
+    match isize::from_str(operand) {
+        Ok(value) => Operand::Value(value),
+        Err(_) => Operand::Register(parse_register(operand)),
+    }
+}
+
+fn parse(file: &str) -> Vec<Instruction> {
+    file.lines()
+        .map(|line| {
+            let mut parts = line.split(" ");
+            let op = parts.next().expect("has an op");
+            let a = parse_register(parts.next().expect("has a register"));
+            let mut b = || parse_operand(parts.next().expect("has a second operand"));
+
+            match op {
+                "inp" => Inp(a),
+                "add" => Add(a, b()),
+                "mul" => Mul(a, b()),
+                "div" => Div(a, b()),
+                "mod" => Mod(a, b()),
+                "eql" => Eql(a, b()),
+                other => panic!("invalid op {:?}", other),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_from_advent_of_code() {
+        let negate = parse("inp x\nmul x -1");
+        assert_eq!(run(&negate, &[7])[X], -7);
+
+        let three_times_larger = parse("inp z\ninp x\nmul z 3\neql z x");
+        assert_eq!(run(&three_times_larger, &[2, 6])[Z], 1);
+        assert_eq!(run(&three_times_larger, &[2, 5])[Z], 0);
+
+        let binary = parse(
+            "inp w\nadd z w\nmod z 2\ndiv w 2\nadd y w\nmod y 2\ndiv w 2\nadd x w\nmod x 2\ndiv w 2\nmod w 2",
+        );
+        assert_eq!(run(&binary, &[13]), [1, 1, 0, 1]);
+    }
+
+    fn monad_block(divisor: isize, check: isize, offset: isize) -> String {
+        format!(
+            "inp w\nmul x 0\nadd x z\nmod x 26\ndiv z {}\nadd x {}\neql x w\neql x 0\nmul y 0\nadd y 25\nmul y x\nadd y 1\nmul z y\nmul y 0\nadd y w\nadd y {}\nmul y x\nadd z y",
+            divisor, check, offset
+        )
+    }
+
+    #[test]
+    fn test_model_number() {
+        let params = [
+            (1, 12, 4),
+            (1, 12, 11),
+            (26, -5, 0),
+            (1, 12, 7),
+            (26, -9, 0),
+            (26, -1, 0),
+            (1, 12, 2),
+            (1, 12, 13),
+            (26, -16, 0),
+            (26, 4, 0),
+            (1, 12, 1),
+            (26, -1, 0),
+            (1, 12, 8),
+            (26, -15, 0),
+        ];
+        let file: Vec<String> = params
+            .iter()
+            .map(|&(divisor, check, offset)| monad_block(divisor, check, offset))
+            .collect();
+        let program = parse(&file.join("\n"));
+        let blocks = monad_blocks(&program);
+        assert_eq!(blocks.len(), 14);
+
+        let largest = model_number(&blocks, true);
+        assert_eq!(digits_to_number(&largest), 63997939699992);
+        assert_eq!(run(&program, &largest)[Z], 0);
+
+        let smallest = model_number(&blocks, false);
+        assert_eq!(digits_to_number(&smallest), 11731414171181);
+        assert_eq!(run(&program, &smallest)[Z], 0);
+
+        // Bumping any digit of the largest number breaks its pair's constraint
+        let mut invalid = largest.clone();
+        invalid[13] -= 1;
+        assert_ne!(run(&program, &invalid)[Z], 0);
+    }
+}
@@ -0,0 +1,121 @@
+fn main() {
+    let sea_floor = load_file("./src/inputs/day25.txt");
+
+    // Part 1
+    println!("Day 25 Pt 1 answer: {:?}", steps_until_stopped(sea_floor));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Space {
+    Empty,
+    East,
+    South,
+}
+
+use Space::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SeaFloor(Vec<Vec<Space>>);
+
+impl SeaFloor {
+    /// The east facing herd moves first, then the south facing herd. Everyone in a herd looks at
+    /// the floor as it was before their herd moved, and the edges wrap around.
+    fn step(&self) -> (SeaFloor, usize) {
+        let (after_east, east_moves) = self.move_herd(East, |(x, y), (w, _h)| ((x + 1) % w, y));
+        let (after_south, south_moves) =
+            after_east.move_herd(South, |(x, y), (_w, h)| (x, (y + 1) % h));
+
+        (after_south, east_moves + south_moves)
+    }
+
+    fn move_herd(
+        &self,
+        herd: Space,
+        next: impl Fn((usize, usize), (usize, usize)) -> (usize, usize),
+    ) -> (SeaFloor, usize) {
+        let size = (self.width(), self.height());
+        let mut moved = self.clone();
+        let mut moves = 0;
+
+        for (y, row) in self.0.iter().enumerate() {
+            for (x, &space) in row.iter().enumerate() {
+                let (next_x, next_y) = next((x, y), size);
+
+                if space == herd && self.0[next_y][next_x] == Empty {
+                    moved.0[y][x] = Empty;
+                    moved.0[next_y][next_x] = herd;
+                    moves += 1;
+                }
+            }
+        }
+
+        (moved, moves)
+    }
+
+    fn width(&self) -> usize {
+        self.0[0].len()
+    }
+
+    fn height(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// The first step on which no sea cucumber moves
+fn steps_until_stopped(mut sea_floor: SeaFloor) -> usize {
+    for step in 1.. {
+        let (next, moves) = sea_floor.step();
+
+        if moves == 0 {
+            return step;
+        }
+
+        sea_floor = next;
+    }
+
+    unreachable!()
+}
+
+fn parse(file: &str) -> SeaFloor {
+    let spaces = file
+        .lines()
+        .map(|line| {
+            line.chars()
+                .map(|c| match c {
+                    '.' => Empty,
+                    '>' => East,
+                    'v' => South,
+                    other => panic!("invalid space {:?}", other),
+                })
+                .collect()
+        })
+        .collect();
+
+    SeaFloor(spaces)
+}
+
+fn load_file(file_name: &str) -> SeaFloor {
+    parse(&std::fs::read_to_string(file_name).expect("file exists"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_herds_move_in_order() {
+        let sea_floor = parse("...>...\n.......\n......>\nv.....>\n......>\n.......\n..vvv..");
+        let (after, _) = sea_floor.step();
+
+        assert_eq!(
+            after,
+            parse("..vv>..\n.......\n>......\nv.....>\n>......\n.......\n....v..")
+        );
+    }
+
+    #[test]
+    fn example_from_advent_of_code() {
+        let sea_floor = load_file("./src/inputs/day25-example.txt");
+        assert_eq!(steps_until_stopped(sea_floor), 58);
+    }
+}
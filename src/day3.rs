@@ -1,114 +1,435 @@
-#![feature(total_cmp)]
-
-mod common;
-use std::cmp::Ordering::*;
 use std::fs::read_to_string;
 
+// Pass `explain` to show how each life support rating was narrowed down one column at a time,
+// optionally followed by `lsb-first` to filter from the rightmost column instead
 fn main() {
-    let inputs = load_inputs("./src/inputs/day3.txt");
+    let report = load_inputs("./src/inputs/day3.txt");
 
-    let most_common: Vec<bool> = most_common_bit_at_index(inputs.iter());
-    let least_common: Vec<bool> = most_common.iter().map(|x| !x).collect();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(|arg| arg.as_str()) == Some("explain") {
+        let bit_order = match args.get(1).map(|arg| arg.as_str()) {
+            None => BitOrder::MostSignificantFirst,
+            Some("lsb-first") => BitOrder::LeastSignificantFirst,
+            Some(other) => panic!("unknown bit order {:?}", other),
+        };
 
-    // Part 1
-    let gamma = common::bits_to_bytes(&most_common);
-    let epsilion = common::bits_to_bytes(&least_common);
+        for (name, criteria) in [
+            ("oxygen generator", Criteria::OXYGEN_GENERATOR),
+            ("CO2 scrubber", Criteria::CO2_SCRUBBER),
+        ] {
+            let rating = find_best_match(
+                &report,
+                &Criteria {
+                    bit_order,
+                    ..criteria
+                },
+            );
+            println!("{}: {:0width$b}", name, rating.value, width = report.width);
+            print!("{}", rating.explain());
+        }
+        return;
+    }
 
-    println!("Day 3 Pt 1: {:?}", gamma * epsilion);
+    // Part 1
+    println!("Day 3 Pt 1: {:?}", power_consumption(&report));
 
     // Part 2
 
-    let oxygen_generator = common::bits_to_bytes(find_best_match(inputs.iter(), true));
+    println!("Day 3 Pt 2: {:?}", life_support_rating(&report))
+}
 
-    let co2_scrubber = common::bits_to_bytes(find_best_match(inputs.iter(), false));
+/// Every line of the report packed into the low `width` bits of a `u64`, the first character of
+/// a line is its most significant bit
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Report {
+    width: usize,
+    lines: Vec<u64>,
+}
 
-    println!("Day 3 Pt 2: {:?}", oxygen_generator * co2_scrubber)
+impl Report {
+    fn mask(&self) -> u64 {
+        u64::MAX >> (64 - self.width)
+    }
 }
 
-fn load_inputs(file_name: &str) -> Vec<Vec<bool>> {
-    let file = read_to_string(file_name).expect("can read file");
+/// Column 0 is the leftmost character of the line
+fn bit_at(line: u64, width: usize, column: usize) -> bool {
+    (line >> (width - 1 - column)) & 1 == 1
+}
 
-    file.lines()
-        .map(|line| {
-            line.chars()
-                .map(|bit| match bit {
-                    '1' => true,
-                    '0' => false,
-                    other => panic!("unexpected input {:?}", other),
-                })
-                .collect()
-        })
-        .collect()
+/// Gamma times epsilon, widened since both can be up to 64 bits
+fn power_consumption(report: &Report) -> u128 {
+    let gamma = most_common_bits(&report.lines, report.width);
+    let epsilion = !gamma & report.mask();
+
+    gamma as u128 * epsilion as u128
 }
 
-fn most_common_bit_at_index<'a>(inputs: impl Iterator<Item = &'a Vec<bool>>) -> Vec<bool> {
-    let inputs: Vec<&'a Vec<bool>> = inputs.collect();
-    let number_of_inputs = inputs.len();
-    let input_width = inputs[0].len();
+/// How many lines have a 1 in each column, in a single pass that only visits set bits
+fn column_counts(lines: &[u64], width: usize) -> Vec<usize> {
+    let mut counts = vec![0; width];
 
-    (0..input_width)
-        .map(|idx| inputs.iter().filter(|bits| bits[idx]).count())
-        .map(move |count| {
-            let ordering = (count as f64).total_cmp(&((number_of_inputs as f64) / 2.0));
+    for &line in lines {
+        let mut remaining = line;
 
-            match ordering {
-                Less => false,
-                Greater | Equal => true,
-            }
+        while remaining != 0 {
+            let shift = remaining.trailing_zeros() as usize;
+            counts[width - 1 - shift] += 1;
+            remaining &= remaining - 1;
+        }
+    }
+
+    counts
+}
+
+/// 1 wins ties
+fn is_one_most_common(ones: usize, total: usize) -> bool {
+    ones * 2 >= total
+}
+
+fn most_common_bits(lines: &[u64], width: usize) -> u64 {
+    column_counts(lines, width)
+        .into_iter()
+        .fold(0, |bits, ones| {
+            (bits << 1) | is_one_most_common(ones, lines.len()) as u64
         })
-        .collect()
 }
 
-fn find_best_match<'a>(
-    inputs: impl Iterator<Item = &'a Vec<bool>>,
-    use_most_common: bool,
-) -> &'a Vec<bool> {
-    let mut inputs: Vec<&Vec<bool>> = inputs.collect();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Keep {
+    MostCommon,
+    LeastCommon,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BitOrder {
+    /// Left to right, the way the puzzle reads the lines
+    MostSignificantFirst,
+    LeastSignificantFirst,
+}
 
-    let mut current_idx = 0;
+/// How a rating narrows down the report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Criteria {
+    keep: Keep,
+    /// The bit to keep when there are as many ones as zeros
+    tie_break: bool,
+    /// Where filtering starts, counted in `bit_order`. Columns wrap around so every column is
+    /// considered once.
+    start_column: usize,
+    bit_order: BitOrder,
+}
 
-    while inputs.len() > 1 {
-        let mut most_common = most_common_bit_at_index(inputs.iter().map(|&x| x));
+impl Criteria {
+    const OXYGEN_GENERATOR: Criteria = Criteria {
+        keep: Keep::MostCommon,
+        tie_break: true,
+        start_column: 0,
+        bit_order: BitOrder::MostSignificantFirst,
+    };
 
-        if !use_most_common {
-            for bit in most_common.iter_mut() {
-                *bit = !*bit;
-            }
+    const CO2_SCRUBBER: Criteria = Criteria {
+        keep: Keep::LeastCommon,
+        tie_break: false,
+        start_column: 0,
+        bit_order: BitOrder::MostSignificantFirst,
+    };
+
+    fn bit_to_keep(&self, ones: usize, zeros: usize) -> bool {
+        use std::cmp::Ordering::*;
+
+        match (ones.cmp(&zeros), self.keep) {
+            (Equal, _) => self.tie_break,
+            (Greater, Keep::MostCommon) | (Less, Keep::LeastCommon) => true,
+            (Less, Keep::MostCommon) | (Greater, Keep::LeastCommon) => false,
         }
+    }
+
+    /// Columns in the order they are filtered on, using the same numbering as `bit_at`
+    fn columns(&self, width: usize) -> impl Iterator<Item = usize> {
+        let order = self.bit_order;
+        let start = self.start_column;
 
-        inputs.retain(|bits| bits[current_idx] == most_common[current_idx]);
-        current_idx += 1;
+        (0..width)
+            .map(move |offset| (start + offset) % width)
+            .map(move |column| match order {
+                BitOrder::MostSignificantFirst => column,
+                BitOrder::LeastSignificantFirst => width - 1 - column,
+            })
     }
+}
+
+/// What happened when filtering on a single column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FilterStep {
+    column: usize,
+    ones: usize,
+    zeros: usize,
+    kept: bool,
+    remaining: usize,
+    /// Every remaining line had the same bit, so filtering on it would keep all of them or none
+    skipped: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rating {
+    value: u64,
+    trace: Vec<FilterStep>,
+}
 
-    inputs[0]
+impl Rating {
+    fn explain(&self) -> String {
+        self.trace
+            .iter()
+            .map(|step| match step.skipped {
+                true => format!(
+                    "column {}: {} ones, {} zeros, skipped, {} left\n",
+                    step.column, step.ones, step.zeros, step.remaining
+                ),
+                false => format!(
+                    "column {}: {} ones, {} zeros, kept {}, {} left\n",
+                    step.column, step.ones, step.zeros, step.kept as u8, step.remaining
+                ),
+            })
+            .collect()
+    }
+}
+
+/// Narrow down the lines one column at a time, only counting that column among the lines that
+/// are left, until a single line remains. Columns where every remaining line agrees are skipped,
+/// otherwise keeping the least common bit would throw every line away.
+fn find_best_match(report: &Report, criteria: &Criteria) -> Rating {
+    let mut lines = report.lines.clone();
+    let mut trace = Vec::new();
+
+    for column in criteria.columns(report.width) {
+        if lines.len() <= 1 {
+            break;
+        }
+
+        let ones = lines
+            .iter()
+            .filter(|&&line| bit_at(line, report.width, column))
+            .count();
+        let zeros = lines.len() - ones;
+        let skipped = ones == 0 || zeros == 0;
+        let kept = if skipped {
+            ones > 0
+        } else {
+            criteria.bit_to_keep(ones, zeros)
+        };
+
+        lines.retain(|&line| bit_at(line, report.width, column) == kept);
+        trace.push(FilterStep {
+            column,
+            ones,
+            zeros,
+            kept,
+            remaining: lines.len(),
+            skipped,
+        });
+    }
+
+    // Skipped columns never empty the lines, and anything left after every column has been
+    // considered is the same line repeated
+    Rating {
+        value: *lines.first().expect("report isn't empty"),
+        trace,
+    }
+}
+
+/// The oxygen generator rating times the CO2 scrubber rating, widened like `power_consumption`
+fn life_support_rating(report: &Report) -> u128 {
+    let oxygen_generator = find_best_match(report, &Criteria::OXYGEN_GENERATOR);
+    let co2_scrubber = find_best_match(report, &Criteria::CO2_SCRUBBER);
+
+    oxygen_generator.value as u128 * co2_scrubber.value as u128
+}
+
+fn load_inputs(file_name: &str) -> Report {
+    let file = read_to_string(file_name).expect("can read file");
+    let width = file.lines().next().expect("has at least one line").len();
+    assert!(0 < width && width <= 64, "lines fit in a u64");
+
+    let lines = file
+        .lines()
+        .map(|line| {
+            assert_eq!(line.len(), width, "lines are all the same width");
+
+            line.chars().fold(0, |bits, bit| {
+                let bit = match bit {
+                    '1' => 1,
+                    '0' => 0,
+                    other => panic!("unexpected input {:?}", other),
+                };
+
+                (bits << 1) | bit
+            })
+        })
+        .collect();
+
+    Report { width, lines }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn bools_to_ints(input: &[bool]) -> Vec<u8> {
-        input.iter().map(|&x| if x { 1 } else { 0 }).collect()
-    }
-
     #[test]
     fn example_from_advent_of_code() {
-        let inputs = load_inputs("./src/inputs/day3-example.txt");
+        let report = load_inputs("./src/inputs/day3-example.txt");
+        assert_eq!(report.width, 5);
+        assert_eq!(report.lines[0], 0b00100);
 
         // Part 1
-        let most_common: Vec<bool> = most_common_bit_at_index(inputs.iter());
-        assert_eq!(bools_to_ints(&most_common), vec![1, 0, 1, 1, 0]);
-        assert_eq!(common::bits_to_bytes(&most_common), 22);
+        assert_eq!(column_counts(&report.lines, 5), vec![7, 5, 8, 7, 5]);
 
-        let least_common: Vec<bool> = most_common.iter().map(|&x| !x).collect();
-        assert_eq!(bools_to_ints(&least_common), vec![0, 1, 0, 0, 1]);
-        assert_eq!(common::bits_to_bytes(&least_common), 9);
+        let most_common = most_common_bits(&report.lines, report.width);
+        assert_eq!(most_common, 0b10110);
+        assert_eq!(most_common, 22);
+
+        let least_common = !most_common & report.mask();
+        assert_eq!(least_common, 0b01001);
+        assert_eq!(least_common, 9);
+        assert_eq!(power_consumption(&report), 198);
 
         // Part 2
-        let oxygen = find_best_match(inputs.iter(), true);
-        assert_eq!(bools_to_ints(oxygen), vec![1, 0, 1, 1, 1]);
+        let oxygen = find_best_match(&report, &Criteria::OXYGEN_GENERATOR);
+        assert_eq!(oxygen.value, 0b10111);
+
+        let carbon = find_best_match(&report, &Criteria::CO2_SCRUBBER);
+        assert_eq!(carbon.value, 0b01010);
+        assert_eq!(life_support_rating(&report), 230);
+    }
+
+    #[test]
+    fn test_rating_trace() {
+        let report = load_inputs("./src/inputs/day3-example.txt");
+
+        // The walk through from the puzzle text
+        let oxygen = find_best_match(&report, &Criteria::OXYGEN_GENERATOR);
+        let remaining: Vec<usize> = oxygen.trace.iter().map(|step| step.remaining).collect();
+        assert_eq!(remaining, vec![7, 4, 3, 2, 1]);
+        assert_eq!(
+            oxygen.trace[4],
+            FilterStep {
+                column: 4,
+                ones: 1,
+                zeros: 1,
+                kept: true,
+                remaining: 1,
+                skipped: false,
+            }
+        );
+        assert!(oxygen
+            .explain()
+            .starts_with("column 0: 7 ones, 5 zeros, kept 1, 7 left\n"));
+
+        let carbon = find_best_match(&report, &Criteria::CO2_SCRUBBER);
+        let remaining: Vec<usize> = carbon.trace.iter().map(|step| step.remaining).collect();
+        assert_eq!(remaining, vec![5, 2, 1]);
+    }
+
+    #[test]
+    fn test_other_criteria() {
+        let report = load_inputs("./src/inputs/day3-example.txt");
+
+        // Breaking ties the other way only changes the 4th column of the oxygen rating
+        let oxygen = find_best_match(
+            &report,
+            &Criteria {
+                tie_break: false,
+                ..Criteria::OXYGEN_GENERATOR
+            },
+        );
+        assert_eq!(oxygen.value, 0b10110);
+
+        // Filtering right to left starts with the last character of each line
+        let reversed = find_best_match(
+            &report,
+            &Criteria {
+                bit_order: BitOrder::LeastSignificantFirst,
+                ..Criteria::OXYGEN_GENERATOR
+            },
+        );
+        assert_eq!(reversed.trace[0].column, 4);
+
+        // Starting in the middle wraps back around to the first column
+        let criteria = Criteria {
+            start_column: 3,
+            ..Criteria::OXYGEN_GENERATOR
+        };
+        assert!(criteria.columns(5).eq([3, 4, 0, 1, 2]));
+    }
+
+    #[test]
+    fn test_columns_where_every_line_agrees() {
+        let report = Report {
+            width: 3,
+            lines: vec![0b101, 0b101, 0b100],
+        };
+
+        // The first two columns are the same on every line, so only the last one filters
+        let carbon = find_best_match(&report, &Criteria::CO2_SCRUBBER);
+        assert_eq!(carbon.value, 0b100);
+        assert_eq!(
+            carbon.trace[0],
+            FilterStep {
+                column: 0,
+                ones: 3,
+                zeros: 0,
+                kept: true,
+                remaining: 3,
+                skipped: true,
+            }
+        );
+        assert!(carbon.trace[1].skipped);
+        assert_eq!(carbon.trace[2].remaining, 1);
+        assert!(carbon
+            .explain()
+            .starts_with("column 0: 3 ones, 0 zeros, skipped, 3 left\n"));
+
+        // Duplicates are still there once every column has been considered
+        let report = Report {
+            width: 3,
+            lines: vec![0b011, 0b011],
+        };
+        for criteria in [Criteria::OXYGEN_GENERATOR, Criteria::CO2_SCRUBBER] {
+            let rating = find_best_match(&report, &criteria);
+            assert_eq!(rating.value, 0b011);
+            assert!(rating.trace.iter().all(|step| step.skipped));
+        }
+    }
+
+    #[test]
+    fn test_wide_lines() {
+        let report = Report {
+            width: 64,
+            lines: vec![u64::MAX, 1 << 63, 1],
+        };
+
+        assert_eq!(report.mask(), u64::MAX);
+        assert_eq!(column_counts(&report.lines, 64)[0], 2);
+        assert_eq!(column_counts(&report.lines, 64)[63], 2);
+        assert_eq!(most_common_bits(&report.lines, 64), (1 << 63) | 1);
+        assert_eq!(
+            find_best_match(&report, &Criteria::OXYGEN_GENERATOR).value,
+            u64::MAX
+        );
+        assert_eq!(find_best_match(&report, &Criteria::CO2_SCRUBBER).value, 1);
+
+        // Gamma is 1000...0001 and epsilon 0111...1110, far too big to multiply as u64s
+        let gamma = (1u64 << 63) | 1;
+        assert_eq!(power_consumption(&report), gamma as u128 * (!gamma) as u128);
+        assert!(power_consumption(&report) > u64::MAX as u128);
 
-        let carbon = find_best_match(inputs.iter(), false);
-        assert_eq!(bools_to_ints(carbon), vec![0, 1, 0, 1, 0]);
+        let report = Report {
+            width: 64,
+            lines: vec![u64::MAX, u64::MAX, u64::MAX >> 1],
+        };
+        assert_eq!(
+            life_support_rating(&report),
+            u64::MAX as u128 * (u64::MAX >> 1) as u128
+        );
     }
 }
@@ -1,101 +1,523 @@
-// Cleaned up input using the following vim commands
-// :2,$s/  \(\d\)/ 0\1/g
-// :2,$s/^ \(\d\)/0\1/g
-
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
 use std::fs::read_to_string;
 use std::str::FromStr;
 
 fn main() {
     let (choosen_numbers, boards) = load_file("./src/inputs/day4.txt");
+    let simulation = simulate(&choosen_numbers, &boards);
 
     // Part 1
-    let (numbers, winner): (&[u64], &Board) = (5..)
-        .map(|i| &choosen_numbers[..=i])
-        .flat_map(|nums| boards.iter().map(move |board| (nums, board)))
-        .find(|(nums, board)| board.are_numbers_winners_for_board(nums))
-        .expect("at least one board wins");
+    let first = simulation.nth_winner(0).expect("at least one board wins");
+    println!("Day 4 Pt 1 answer: {:?}", first.score());
 
-    let sum_of_unmarked_numbers: u64 = winner.0.iter().filter(|num| !numbers.contains(num)).sum();
+    // Part 2
+    let last = simulation.wins.last().expect("at least one board wins");
+    println!("Day 4 Pt 2 answer: {:?}", last.score());
+}
 
-    println!(
-        "Day 4 Pt 1 answer: {:?}",
-        sum_of_unmarked_numbers * numbers.last().expect("numbers is not empty")
-    );
+/// A square board of numbers stored row by row
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Board {
+    size: usize,
+    numbers: Vec<u64>,
+}
 
-    // Part 2
-    let (numbers, last_winner) = boards
-        .iter()
-        .map(|board| {
-            let winning_numbers = (0..)
-                .map(|i| &choosen_numbers[..=i])
-                .find(|nums| board.are_numbers_winners_for_board(nums))
-                .expect("everyboard has a winning set");
+/// Marks for a single board, kept up to date as numbers are drawn
+#[derive(Debug, Clone)]
+struct Marks {
+    marked: Vec<bool>,
+    row_hits: Vec<usize>,
+    column_hits: Vec<usize>,
+    unmarked_sum: u64,
+    has_won: bool,
+}
+
+impl Marks {
+    fn new(board: &Board) -> Self {
+        Marks {
+            marked: vec![false; board.numbers.len()],
+            row_hits: vec![0; board.size],
+            column_hits: vec![0; board.size],
+            unmarked_sum: board.numbers.iter().sum(),
+            has_won: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Win {
+    board: usize,
+    /// Index into the drawn numbers of the number that completed the board
+    draw: usize,
+    number: u64,
+    line: Line,
+    unmarked_sum: u64,
+}
+
+/// The completed row or column, counting from 0. If the winning number completes both the row
+/// is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Line {
+    Row(usize),
+    Column(usize),
+}
+
+impl Win {
+    fn score(&self) -> u64 {
+        self.unmarked_sum * self.number
+    }
+}
+
+/// Plays every board at once, each drawn number only touches the cells that hold it
+struct Bingo<'a> {
+    boards: &'a [Board],
+    marks: Vec<Marks>,
+    /// Every (board, cell) holding each number
+    cells: HashMap<u64, Vec<(usize, usize)>>,
+    draws: usize,
+}
+
+impl<'a> Bingo<'a> {
+    fn new(boards: &'a [Board]) -> Self {
+        let mut cells: HashMap<u64, Vec<(usize, usize)>> = HashMap::new();
+
+        for (board_idx, board) in boards.iter().enumerate() {
+            for (cell, &number) in board.numbers.iter().enumerate() {
+                cells.entry(number).or_default().push((board_idx, cell));
+            }
+        }
+
+        Bingo {
+            boards,
+            marks: boards.iter().map(Marks::new).collect(),
+            cells,
+            draws: 0,
+        }
+    }
+
+    /// Mark `number` everywhere it appears, returning the boards that won because of it in board
+    /// order. Boards that have already won keep getting marked but don't win again.
+    fn draw(&mut self, number: u64) -> Vec<Win> {
+        let draw = self.draws;
+        self.draws += 1;
+
+        let mut wins = Vec::new();
+
+        for &(board_idx, cell) in self.cells.get(&number).into_iter().flatten() {
+            let size = self.boards[board_idx].size;
+            let marks = &mut self.marks[board_idx];
+
+            if marks.marked[cell] {
+                continue;
+            }
+
+            marks.marked[cell] = true;
+            marks.unmarked_sum -= number;
+            marks.row_hits[cell / size] += 1;
+            marks.column_hits[cell % size] += 1;
+
+            let (row, column) = (cell / size, cell % size);
+            let line = if marks.row_hits[row] == size {
+                Some(Line::Row(row))
+            } else if marks.column_hits[column] == size {
+                Some(Line::Column(column))
+            } else {
+                None
+            };
 
-            (winning_numbers, board)
+            match line {
+                Some(line) if !marks.has_won => {
+                    marks.has_won = true;
+                    wins.push(Win {
+                        board: board_idx,
+                        draw,
+                        number,
+                        line,
+                        unmarked_sum: marks.unmarked_sum,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        wins
+    }
+}
+
+/// How a single board did over the whole game
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BoardReport {
+    win: Option<Win>,
+    /// The board as it was when it won, or after the last draw if it never did
+    rendered: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Simulation {
+    /// Every win in the order it happened
+    wins: Vec<Win>,
+    /// Indexed by board
+    boards: Vec<BoardReport>,
+}
+
+impl Simulation {
+    /// The board that wins `n`th, starting from 0
+    fn nth_winner(&self, n: usize) -> Option<&Win> {
+        self.wins.get(n)
+    }
+}
+
+fn simulate(numbers: &[u64], boards: &[Board]) -> Simulation {
+    let mut bingo = Bingo::new(boards);
+    let mut wins = Vec::new();
+    let mut rendered: Vec<Option<String>> = vec![None; boards.len()];
+
+    for &number in numbers {
+        for win in bingo.draw(number) {
+            rendered[win.board] = Some(render(&boards[win.board], &bingo.marks[win.board]));
+            wins.push(win);
+        }
+    }
+
+    let reports = boards
+        .iter()
+        .enumerate()
+        .zip(rendered)
+        .map(|((idx, board), rendered)| BoardReport {
+            win: wins.iter().find(|win| win.board == idx).cloned(),
+            rendered: rendered.unwrap_or_else(|| render(board, &bingo.marks[idx])),
         })
-        .max_by_key(|(nums, _board)| nums.len())
-        .expect("there is more than one board");
+        .collect();
 
-    let sum_of_unmarked_numbers: u64 = last_winner
-        .0
+    Simulation {
+        wins,
+        boards: reports,
+    }
+}
+
+/// One line per row, marked numbers are wrapped in brackets
+fn render(board: &Board, marks: &Marks) -> String {
+    let width = board
+        .numbers
         .iter()
-        .filter(|num| !numbers.contains(num))
-        .sum();
+        .map(|num| num.to_string().len())
+        .max()
+        .unwrap_or(0);
+
+    board
+        .numbers
+        .chunks(board.size)
+        .zip(marks.marked.chunks(board.size))
+        .map(|(numbers, marked)| {
+            let cells: Vec<String> = numbers
+                .iter()
+                .zip(marked)
+                .map(|(num, &marked)| {
+                    if marked {
+                        format!("[{:>width$}]", num, width = width)
+                    } else {
+                        format!(" {:>width$} ", num, width = width)
+                    }
+                })
+                .collect();
 
-    println!(
-        "Day 4 Pt 2 answer: {:?}",
-        sum_of_unmarked_numbers * numbers.last().expect("numbers is not empty")
-    );
+            cells.join(" ").trim_end().to_string() + "\n"
+        })
+        .collect()
 }
 
-#[derive(Debug)]
-struct Board(Vec<u64>);
+/// Where the input stopped making sense, `line` counts from 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ParseError {
+    line: usize,
+    kind: ParseErrorKind,
+}
 
-impl Board {
-    fn are_numbers_winners_for_board(&self, nums: &[u64]) -> bool {
-        self.groups()
-            .any(|mut group| group.all(|num| nums.contains(&num)))
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ParseErrorKind {
+    MissingNumbers,
+    InvalidNumber(String),
+    /// A row with a different number of columns than the board's first row
+    RaggedRow {
+        expected: usize,
+        found: usize,
+    },
+    NotSquare {
+        rows: usize,
+        columns: usize,
+    },
+    DuplicateNumber(u64),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(fmt, "line {}: ", self.line)?;
+
+        match &self.kind {
+            ParseErrorKind::MissingNumbers => write!(fmt, "expected the drawn numbers"),
+            ParseErrorKind::InvalidNumber(num) => write!(fmt, "invalid number {:?}", num),
+            ParseErrorKind::RaggedRow { expected, found } => {
+                write!(fmt, "expected {} numbers but found {}", expected, found)
+            }
+            ParseErrorKind::NotSquare { rows, columns } => {
+                write!(fmt, "board has {} rows and {} columns", rows, columns)
+            }
+            ParseErrorKind::DuplicateNumber(num) => {
+                write!(fmt, "{} appears more than once on the board", num)
+            }
+        }
     }
+}
+
+fn parse_number(line: usize, num: &str) -> Result<u64, ParseError> {
+    u64::from_str(num.trim()).map_err(|_| ParseError {
+        line,
+        kind: ParseErrorKind::InvalidNumber(num.to_string()),
+    })
+}
 
-    fn groups(&self) -> impl Iterator<Item = impl Iterator<Item = u64> + '_> + '_ {
-        let mut groups = Vec::new();
+/// The drawn numbers followed by boards separated by blank lines, numbers on a board can be
+/// separated by any amount of whitespace
+fn parse(file: &str) -> Result<(Vec<u64>, Vec<Board>), ParseError> {
+    let mut lines = file
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim()))
+        .peekable();
 
-        for row in 0..5 {
-            groups.push(self.0[row * 5..][..5].iter().cloned().collect());
+    let (line, numbers) = lines.find(|(_, line)| !line.is_empty()).ok_or(ParseError {
+        line: 1,
+        kind: ParseErrorKind::MissingNumbers,
+    })?;
+    let chosen_numbers = numbers
+        .split(",")
+        .map(|num| parse_number(line, num))
+        .collect::<Result<Vec<u64>, _>>()?;
+
+    let mut boards = Vec::new();
+
+    loop {
+        // Skip to the start of the next board
+        while let Some((_, "")) = lines.peek() {
+            lines.next();
+        }
+
+        let first_line = match lines.peek() {
+            Some(&(line, _)) => line,
+            None => break,
+        };
+
+        let mut numbers = Vec::new();
+        let mut seen = HashSet::new();
+        let mut rows = 0;
+        let mut columns = 0;
+
+        while let Some((line, row)) = lines.next_if(|(_, row)| !row.is_empty()) {
+            let row = row
+                .split_whitespace()
+                .map(|num| parse_number(line, num))
+                .collect::<Result<Vec<u64>, _>>()?;
+
+            if rows == 0 {
+                columns = row.len();
+            } else if row.len() != columns {
+                let kind = ParseErrorKind::RaggedRow {
+                    expected: columns,
+                    found: row.len(),
+                };
+                return Err(ParseError { line, kind });
+            }
+
+            if let Some(&duplicate) = row.iter().find(|&&num| !seen.insert(num)) {
+                let kind = ParseErrorKind::DuplicateNumber(duplicate);
+                return Err(ParseError { line, kind });
+            }
+
+            numbers.extend(row);
+            rows += 1;
         }
 
-        for col in 0..5 {
-            let column = (0..5).map(|row_num| self.0[row_num * 5 + col]).collect();
-            groups.push(column);
+        if rows != columns {
+            let kind = ParseErrorKind::NotSquare { rows, columns };
+            return Err(ParseError {
+                line: first_line,
+                kind,
+            });
         }
 
-        groups.into_iter().map(|group: Vec<u64>| group.into_iter())
+        boards.push(Board {
+            size: rows,
+            numbers,
+        });
     }
+
+    Ok((chosen_numbers, boards))
 }
 
 fn load_file(file_name: &str) -> (Vec<u64>, Vec<Board>) {
     let file = read_to_string(file_name).expect("can read file");
-    let mut lines = file.lines();
+    parse(&file).unwrap_or_else(|err| panic!("{} {}", file_name, err))
+}
 
-    let chosen_numbers: Vec<u64> = lines
-        .next()
-        .expect("file isn't empty")
-        .split(",")
-        .map(|num| u64::from_str(num).expect("valid numbers in chosen_numbers"))
-        .collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let mut boards = Vec::new();
+    fn board(size: usize, numbers: impl IntoIterator<Item = u64>) -> Board {
+        Board {
+            size,
+            numbers: numbers.into_iter().collect(),
+        }
+    }
+
+    #[test]
+    fn test_wins_in_order() {
+        let boards = [
+            board(2, [1, 2, 3, 4]),
+            board(3, [1, 5, 6, 7, 2, 8, 9, 10, 3]),
+            board(2, [5, 7, 2, 1]),
+        ];
 
-    while let Some("") = lines.next() {
-        let board = (0..5)
-            .flat_map(|_| {
-                let line = lines.next().expect("is a complete board");
-                line.split(" ")
-                    .map(|num| u64::from_str(num).expect("valid num"))
-            })
-            .collect();
-        boards.push(Board(board));
+        let wins = simulate(&[1, 2, 5, 3, 7, 9], &boards).wins;
+
+        // Boards that win on the same draw come out in board order, and diagonals don't count
+        assert_eq!(
+            wins,
+            vec![
+                Win {
+                    board: 0,
+                    draw: 1,
+                    number: 2,
+                    line: Line::Row(0),
+                    unmarked_sum: 7
+                },
+                Win {
+                    board: 2,
+                    draw: 1,
+                    number: 2,
+                    line: Line::Row(1),
+                    unmarked_sum: 12
+                },
+                Win {
+                    board: 1,
+                    draw: 5,
+                    number: 9,
+                    line: Line::Column(0),
+                    unmarked_sum: 24
+                },
+            ]
+        );
+        assert_eq!(wins[0].score(), 14);
+    }
+
+    #[test]
+    fn test_parse() {
+        let input =
+            "7,4,9 ,5\n\n\n22 13 17\n 8  2 23\n21  9 14\n\n   3 15  0\n 9 18 13\n19  8  7\n\n";
+        let (numbers, boards) = parse(input).expect("valid input");
+
+        assert_eq!(numbers, vec![7, 4, 9, 5]);
+        assert_eq!(boards[0], board(3, [22, 13, 17, 8, 2, 23, 21, 9, 14]));
+        assert_eq!(boards[1], board(3, [3, 15, 0, 9, 18, 13, 19, 8, 7]));
     }
 
-    (chosen_numbers, boards)
+    #[test]
+    fn test_parse_errors() {
+        let error = |input| parse(input).expect_err("invalid input");
+
+        assert_eq!(
+            error("1,2\n\n1 2\n3 x"),
+            ParseError {
+                line: 4,
+                kind: ParseErrorKind::InvalidNumber("x".to_string())
+            }
+        );
+        assert_eq!(
+            error("1,2\n\n1 2\n3 4\n\n1 2\n3"),
+            ParseError {
+                line: 7,
+                kind: ParseErrorKind::RaggedRow {
+                    expected: 2,
+                    found: 1
+                }
+            }
+        );
+        assert_eq!(
+            error("1,2\n\n1 2\n3 4\n5 6"),
+            ParseError {
+                line: 3,
+                kind: ParseErrorKind::NotSquare {
+                    rows: 3,
+                    columns: 2
+                }
+            }
+        );
+        assert_eq!(
+            error("1,2\n\n1 2\n3 1"),
+            ParseError {
+                line: 4,
+                kind: ParseErrorKind::DuplicateNumber(1)
+            }
+        );
+        assert_eq!(
+            error("\n\n").to_string(),
+            "line 1: expected the drawn numbers"
+        );
+    }
+
+    #[test]
+    fn example_from_advent_of_code() {
+        let (numbers, boards) = load_file("./src/inputs/day4-example.txt");
+        let simulation = simulate(&numbers, &boards);
+
+        let order: Vec<usize> = simulation.wins.iter().map(|win| win.board).collect();
+        assert_eq!(order, vec![2, 0, 1]);
+
+        let first = simulation.nth_winner(0).expect("someone wins");
+        assert_eq!((first.draw, first.number), (11, 24));
+        assert_eq!(first.line, Line::Row(0));
+        assert_eq!(first.unmarked_sum, 188);
+        assert_eq!(first.score(), 4512);
+
+        let last = simulation.nth_winner(2).expect("everyone wins");
+        assert_eq!(last.line, Line::Column(2));
+        assert_eq!(last.score(), 1924);
+        assert_eq!(simulation.nth_winner(3), None);
+
+        let report = &simulation.boards[2];
+        assert_eq!(report.win, Some(*first));
+        assert_eq!(
+            report.rendered,
+            [
+                "[14] [21] [17] [24] [ 4]",
+                " 10   16   15  [ 9]  19",
+                " 18    8  [23]  26   20",
+                " 22  [11]  13    6  [ 5]",
+                "[ 2] [ 0]  12    3  [ 7]",
+            ]
+            .join("\n")
+                + "\n"
+        );
+    }
+
+    #[test]
+    fn test_board_that_never_wins() {
+        let boards = [board(2, [1, 2, 3, 4]), board(2, [5, 6, 7, 8])];
+        let simulation = simulate(&[1, 3, 6], &boards);
+
+        assert_eq!(simulation.boards[1].win, None);
+        assert_eq!(simulation.boards[1].rendered, " 5  [6]\n 7   8\n");
+    }
+
+    #[test]
+    fn test_winning_column() {
+        let boards = [board(3, [1, 2, 3, 4, 5, 6, 7, 8, 9])];
+
+        assert!(simulate(&[1, 4], &boards).wins.is_empty());
+        assert_eq!(
+            simulate(&[1, 4, 7], &boards).wins[0].unmarked_sum,
+            2 + 3 + 5 + 6 + 8 + 9
+        );
+    }
 }
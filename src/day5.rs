@@ -1,49 +1,110 @@
 #![allow(dead_code)]
 
+use std::fmt::Write;
 use std::str::FromStr;
 use std::{collections::HashMap, fs::read_to_string};
 
+// Optionally pass `analytic`, `dense` or `auto` (the default) to pick how overlaps are counted,
+// `render x0,y0 x1,y1` to print the diagram of every segment inside that box, or `heatmap` to
+// write heatmaps of both parts to ./output
 fn main() {
     let line_segments = load_file("./src/inputs/day5.txt");
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-    // Part 1
-    let mut point_counts = HashMap::new();
-
-    line_segments
+    let straight: Vec<LineSegment> = line_segments
         .iter()
         .filter(|segment| matches!(segment.direction(), Vertical | Horizontal))
-        .flat_map(|segment| segment.points())
-        .for_each(|point| {
-            let count = point_counts.entry(point).or_insert(0);
-            *count += 1;
-        });
+        .cloned()
+        .collect();
+
+    match args.first().map(|arg| arg.as_str()) {
+        Some("render") => {
+            let corners: Vec<Point> = args[1..]
+                .iter()
+                .map(|corner| Point::from_str(corner).expect("valid corner"))
+                .collect();
+            let (a, b) = match corners[..] {
+                [a, b] => (a, b),
+                _ => panic!("render takes two corners, e.g. `render 0,0 9,9`"),
+            };
+            let bounds = Bounds {
+                min: Point {
+                    x: a.x.min(b.x),
+                    y: a.y.min(b.y),
+                },
+                max: Point {
+                    x: a.x.max(b.x),
+                    y: a.y.max(b.y),
+                },
+            };
+            check_drawable(&bounds);
+            print!("{}", Diagram::new(&line_segments, bounds).render());
+            return;
+        }
+        Some("heatmap") => {
+            // Heatmaps of both parts, to check the diagonals land where they should
+            std::fs::create_dir_all("./output").expect("can create output dir");
+            for (name, segments) in [("pt1", &straight), ("pt2", &line_segments)] {
+                let bounds = Bounds::of(segments).expect("has segments");
+                check_drawable(&bounds);
+                std::fs::write(
+                    format!("./output/day5-{}.pgm", name),
+                    Diagram::new(segments, bounds).to_pgm(),
+                )
+                .expect("can write pgm");
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    let backend = args
+        .first()
+        .map(|name| Backend::from_str(name).expect("valid backend"))
+        .unwrap_or(Backend::Auto);
 
-    let answer = point_counts.into_iter().filter(|&(_k, v)| v > 1).count();
+    // Part 1
+    let answer = count_overlaps_with(&straight, backend);
 
     println!("Day 5 pt 1 answer: {:?}", answer);
 
     // Part 2
-    let mut point_counts = HashMap::new();
-
-    line_segments
-        .iter()
-        .flat_map(|segment| segment.points())
-        .for_each(|point| {
-            let count = point_counts.entry(point).or_insert(0);
-            *count += 1;
-        });
-
-    let answer = point_counts.into_iter().filter(|&(_k, v)| v > 1).count();
+    let answer = count_overlaps_with(&line_segments, backend);
 
     println!("Day 5 pt 2 answer: {:?}", answer);
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct Point {
     x: i64,
     y: i64,
 }
 
+impl Point {
+    fn stepped(self, (dx, dy): (i128, i128), n: i64) -> Point {
+        Point {
+            x: self.x + n * dx as i64,
+            y: self.y + n * dy as i64,
+        }
+    }
+}
+
+impl FromStr for Point {
+    type Err = String;
+
+    /// Parses `x,y`
+    fn from_str(point: &str) -> Result<Self, Self::Err> {
+        let mut components = point
+            .split(",")
+            .map(|num| i64::from_str(num).map_err(|err| format!("{:?}: {}", num, err)));
+
+        let x = components.next().ok_or("has an x")??;
+        let y = components.next().ok_or("has a y")??;
+
+        Ok(Point { x, y })
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct LineSegment {
     start: Point,
@@ -56,38 +117,67 @@ enum Direction {
     Vertical,
     UpDiagonal,
     DownDiagonal,
+    /// Any other slope, only the lattice points it passes through exactly are on the segment
+    Sloped,
 }
 
 use Direction::*;
 
 impl LineSegment {
-    fn points(&self) -> impl Iterator<Item = Point> + '_ {
-        let x1 = self.start.x.min(self.end.x);
-        let x2 = self.start.x.max(self.end.x);
-
-        let y1 = self.start.y.min(self.end.y);
-        let y2 = self.start.y.max(self.end.y);
-
-        match self.direction() {
-            Horizontal => (x1..=x2)
-                .map(|x| Point { x, y: y1 })
-                .collect::<Vec<_>>()
-                .into_iter(),
-            Vertical => (y1..=y2)
-                .map(|y| Point { x: x1, y })
-                .collect::<Vec<_>>()
-                .into_iter(),
-            UpDiagonal => (x1..=x2)
-                .zip(y1..=y2)
-                .map(|(x, y)| Point { x, y })
-                .collect::<Vec<_>>()
-                .into_iter(),
-            DownDiagonal => (x1..=x2)
-                .zip((y1..=y2).rev())
-                .map(|(x, y)| Point { x, y })
-                .collect::<Vec<_>>()
-                .into_iter(),
+    /// Every lattice point on the segment, from the end `step` points away from. Walking in
+    /// steps reduced by the gcd lands exactly on the lattice points like Bresenham's algorithm
+    /// would, but never rounds onto points the segment only passes near.
+    fn points(&self) -> impl Iterator<Item = Point> {
+        let (start, step, steps) = self.walk();
+        (0..=steps).map(move |n| start.stepped(step, n))
+    }
+
+    /// The lattice points of the segment that lie inside `bounds`, in the same order as
+    /// `points`. The segment is clipped first, so points outside are never walked.
+    fn points_within(&self, bounds: &Bounds) -> impl Iterator<Item = Point> {
+        let (start, (dx, dy), steps) = self.walk();
+        let (mut first, mut last) = (0, steps as i128);
+
+        // Narrow `first..=last` to the steps where each coordinate lands inside the box
+        for (from, step, min, max) in [
+            (start.x, dx, bounds.min.x, bounds.max.x),
+            (start.y, dy, bounds.min.y, bounds.max.y),
+        ] {
+            let (from, min, max) = (from as i128, min as i128, max as i128);
+            match step.signum() {
+                0 if !(min..=max).contains(&from) => last = -1,
+                0 => {}
+                1 => {
+                    first = first.max(-(from - min).div_euclid(step));
+                    last = last.min((max - from).div_euclid(step));
+                }
+                _ => {
+                    first = first.max(-(max - from).div_euclid(-step));
+                    last = last.min((from - min).div_euclid(-step));
+                }
+            }
         }
+
+        (first..=last).map(move |n| start.stepped((dx, dy), n as i64))
+    }
+
+    /// The end to start from, the step to take and how many steps reach the other end
+    fn walk(&self) -> (Point, (i128, i128), i64) {
+        let (dx, dy) = self.step();
+        let (start, end) = if position_on_line(self.line_key(), self.start)
+            <= position_on_line(self.line_key(), self.end)
+        {
+            (self.start, self.end)
+        } else {
+            (self.end, self.start)
+        };
+        let steps = if dx != 0 {
+            (end.x - start.x) / dx as i64
+        } else {
+            (end.y - start.y) / dy as i64
+        };
+
+        (start, (dx, dy), steps)
     }
 
     fn direction(&self) -> Direction {
@@ -95,23 +185,409 @@ impl LineSegment {
 
         let x_compare = self.start.x.cmp(&self.end.x);
         let y_compare = self.start.y.cmp(&self.end.y);
+        let is_diagonal = (self.end.x - self.start.x).abs() == (self.end.y - self.start.y).abs();
 
         match (x_compare, y_compare) {
             (Equal, _) => Vertical,
             (_, Equal) => Horizontal,
+            _ if !is_diagonal => Sloped,
             (Greater, Greater) => UpDiagonal,
             (Less, Less) => UpDiagonal,
             (Less, Greater) => DownDiagonal,
             (Greater, Less) => DownDiagonal,
         }
     }
+}
+
+/// Identifies the infinite line a segment lies on, `(dx, dy, c)` where `(dx, dy)` is the
+/// smallest lattice step along the line and `c` is the same for every point on it
+type LineKey = (i128, i128, i128);
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl LineSegment {
+    /// The smallest step between lattice points on the segment, pointing right (or up when
+    /// vertical). Single point segments are treated as horizontal.
+    fn step(&self) -> (i128, i128) {
+        let dx = (self.end.x - self.start.x) as i128;
+        let dy = (self.end.y - self.start.y) as i128;
+
+        let divisor = gcd(dx, dy);
+        if divisor == 0 {
+            return (1, 0);
+        }
+
+        let (dx, dy) = (dx / divisor, dy / divisor);
+        if dx < 0 || (dx == 0 && dy < 0) {
+            (-dx, -dy)
+        } else {
+            (dx, dy)
+        }
+    }
+
+    fn line_key(&self) -> LineKey {
+        let (dx, dy) = self.step();
+        (
+            dx,
+            dy,
+            dy * self.start.x as i128 - dx * self.start.y as i128,
+        )
+    }
+
+    /// Where the segment covers its line in terms of `position_on_line`, smallest first
+    fn interval(&self) -> (i128, i128) {
+        let key = self.line_key();
+        let a = position_on_line(key, self.start);
+        let b = position_on_line(key, self.end);
+        (a.min(b), a.max(b))
+    }
+}
+
+/// Distinct for every point on a line, and lattice points along the line are
+/// `dx * dx + dy * dy` apart
+fn position_on_line((dx, dy, _c): LineKey, point: Point) -> i128 {
+    dx * point.x as i128 + dy * point.y as i128
+}
+
+/// The lattice point where a segment meets a line it isn't part of, if there is one
+fn meets_line(segment: &LineSegment, (dx, dy, c): LineKey) -> Option<Point> {
+    // How far either end is from the line, scaled by the length of `(dx, dy)`
+    let offset = |point: Point| dy * point.x as i128 - dx * point.y as i128 - c;
+    let (from_start, from_end) = (offset(segment.start), offset(segment.end));
+
+    // Parallel, or a single point that is either on the line or not
+    if from_start == from_end {
+        return (from_start == 0).then_some(segment.start);
+    }
+
+    // Both ends on the same side
+    if from_start.signum() == from_end.signum() {
+        return None;
+    }
+
+    // The crossing is `from_start / (from_start - from_end)` of the way along, and has to land
+    // on a lattice point
+    let denominator = from_start - from_end;
+    let x = (segment.end.x - segment.start.x) as i128 * from_start;
+    let y = (segment.end.y - segment.start.y) as i128 * from_start;
+    if x % denominator != 0 || y % denominator != 0 {
+        return None;
+    }
+
+    Some(Point {
+        x: segment.start.x + (x / denominator) as i64,
+        y: segment.start.y + (y / denominator) as i64,
+    })
+}
+
+/// Sweep over a line's intervals, returning the merged stretches covered at least `times` times
+fn covered(intervals: &[(i128, i128)], times: i32) -> Vec<(i128, i128)> {
+    // Starts sort before ends at the same position since intervals are inclusive
+    let mut events: Vec<(i128, i8)> = intervals
+        .iter()
+        .flat_map(|&(start, end)| [(start, -1), (end, 1)])
+        .collect();
+    events.sort();
+
+    let mut stretches: Vec<(i128, i128)> = Vec::new();
+    let mut depth = 0;
+    let mut started_at = None;
+
+    for (position, kind) in events {
+        depth -= kind as i32;
+
+        match (depth >= times, started_at) {
+            (true, None) => started_at = Some(position),
+            (false, Some(start)) => {
+                started_at = None;
+                match stretches.last_mut() {
+                    Some(last) if last.1 >= start => last.1 = last.1.max(position),
+                    _ => stretches.push((start, position)),
+                }
+            }
+            _ => {}
+        }
+    }
 
-    fn slope(&self) -> f64 {
-        (self.end.y - self.start.y) as f64 / (self.end.x - self.start.x) as f64
+    stretches
+}
+
+/// Whether a position falls in one of the sorted, non overlapping stretches
+fn within(stretches: &[(i128, i128)], position: i128) -> bool {
+    let after = stretches.partition_point(|&(start, _end)| start <= position);
+    after > 0 && position <= stretches[after - 1].1
+}
+
+/// Count the points where at least two segments overlap without visiting every point. Segments
+/// on the same line are swept in one dimension. Segments on different lines meet at single
+/// points, which are found one line at a time: every other segment meets the line at most once,
+/// so memory stays proportional to the number of segments. Time is quadratic in the number of
+/// segments, and neither grows with the length of the segments.
+fn count_overlaps(segments: &[LineSegment]) -> usize {
+    let keys: Vec<LineKey> = segments.iter().map(|segment| segment.line_key()).collect();
+
+    let mut lines: HashMap<LineKey, Vec<(i128, i128)>> = HashMap::new();
+    for (segment, &key) in segments.iter().zip(keys.iter()) {
+        lines.entry(key).or_default().push(segment.interval());
     }
 
-    fn y_intercept(&self) -> f64 {
-        (self.start.y as f64) - (self.slope() * self.start.x as f64)
+    let overlaps: HashMap<LineKey, Vec<(i128, i128)>> = lines
+        .iter()
+        .map(|(&key, intervals)| (key, covered(intervals, 2)))
+        .filter(|(_key, stretches)| !stretches.is_empty())
+        .collect();
+
+    let collinear: i128 = overlaps
+        .iter()
+        .flat_map(|(&(dx, dy, _c), stretches)| {
+            stretches
+                .iter()
+                .map(move |(start, end)| (end - start) / (dx * dx + dy * dy) + 1)
+        })
+        .sum();
+
+    let overlaps_at = |key: LineKey, point: Point| matches!(overlaps.get(&key), Some(stretches) if within(stretches, position_on_line(key, point)));
+
+    // Where lines meet, a point is already counted once for every line it overlaps on and needs
+    // counting exactly once overall. Every line through a point sees all the others, so the
+    // smallest one makes the correction.
+    let mut correction: i128 = 0;
+    for (&key, intervals) in lines.iter() {
+        let reach = covered(intervals, 1);
+
+        let mut meetings: Vec<(Point, LineKey)> = segments
+            .iter()
+            .zip(keys.iter())
+            .filter(|(_segment, &other)| other != key)
+            .filter_map(|(segment, &other)| {
+                let point = meets_line(segment, key)?;
+                within(&reach, position_on_line(key, point)).then_some((point, other))
+            })
+            .collect();
+        meetings.sort();
+        meetings.dedup();
+
+        let mut idx = 0;
+        while idx < meetings.len() {
+            let point = meetings[idx].0;
+            let others = meetings[idx..]
+                .iter()
+                .take_while(|(other_point, _other)| *other_point == point)
+                .count();
+            let lines_here = meetings[idx..idx + others]
+                .iter()
+                .map(|&(_point, other)| other);
+
+            // Sorted, so the first is the smallest other line
+            if key < meetings[idx].1 {
+                let overlapping = std::iter::once(key)
+                    .chain(lines_here)
+                    .filter(|&line| overlaps_at(line, point))
+                    .count();
+                correction += 1 - overlapping as i128;
+            }
+
+            idx += others;
+        }
+    }
+
+    (collinear + correction) as usize
+}
+
+/// Largest bounding box, in cells, that `Backend::Auto` will allocate a dense grid for
+const DENSE_GRID_LIMIT: i128 = 1 << 24;
+
+/// Largest box, in cells, that `render` and `heatmap` will draw
+const DIAGRAM_LIMIT: i128 = 1 << 24;
+
+fn check_drawable(bounds: &Bounds) {
+    if bounds.cells() > DIAGRAM_LIMIT {
+        panic!(
+            "{}x{} box is too large to draw, at most {} cells fit",
+            bounds.width(),
+            bounds.height(),
+            DIAGRAM_LIMIT
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    /// `count_overlaps`, for segments spread over a huge area
+    Analytic,
+    /// Walk every point into a grid covering the bounding box, fast when the box is small
+    Dense,
+    /// Dense when the bounding box fits in `DENSE_GRID_LIMIT` cells, otherwise analytic
+    Auto,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "analytic" => Ok(Backend::Analytic),
+            "dense" => Ok(Backend::Dense),
+            "auto" => Ok(Backend::Auto),
+            other => Err(format!("unknown backend {:?}", other)),
+        }
+    }
+}
+
+/// The smallest box holding every segment, both corners included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Bounds {
+    min: Point,
+    max: Point,
+}
+
+impl Bounds {
+    fn of(segments: &[LineSegment]) -> Option<Bounds> {
+        let points = segments
+            .iter()
+            .flat_map(|segment| [segment.start, segment.end]);
+
+        points.fold(None, |bounds, point| {
+            let Bounds { min, max } = bounds.unwrap_or(Bounds {
+                min: point,
+                max: point,
+            });
+
+            Some(Bounds {
+                min: Point {
+                    x: min.x.min(point.x),
+                    y: min.y.min(point.y),
+                },
+                max: Point {
+                    x: max.x.max(point.x),
+                    y: max.y.max(point.y),
+                },
+            })
+        })
+    }
+
+    fn width(&self) -> i128 {
+        (self.max.x - self.min.x) as i128 + 1
+    }
+
+    fn height(&self) -> i128 {
+        (self.max.y - self.min.y) as i128 + 1
+    }
+
+    fn cells(&self) -> i128 {
+        self.width() * self.height()
+    }
+}
+
+fn count_overlaps_with(segments: &[LineSegment], backend: Backend) -> usize {
+    let bounds = match Bounds::of(segments) {
+        Some(bounds) => bounds,
+        None => return 0,
+    };
+
+    match backend {
+        Backend::Analytic => count_overlaps(segments),
+        Backend::Dense => count_overlaps_dense(segments, bounds),
+        Backend::Auto if bounds.cells() <= DENSE_GRID_LIMIT => {
+            count_overlaps_dense(segments, bounds)
+        }
+        Backend::Auto => count_overlaps(segments),
+    }
+}
+
+/// Counts saturate at 2, that's all that matters for overlaps
+fn count_overlaps_dense(segments: &[LineSegment], bounds: Bounds) -> usize {
+    let cells = usize::try_from(bounds.cells()).expect("grid fits in memory");
+    let width = bounds.width() as usize;
+    let mut grid = vec![0u8; cells];
+
+    for point in segments.iter().flat_map(|segment| segment.points()) {
+        let x = (point.x - bounds.min.x) as usize;
+        let y = (point.y - bounds.min.y) as usize;
+        let count = &mut grid[y * width + x];
+        *count = (*count + 1).min(2);
+    }
+
+    grid.into_iter().filter(|&count| count == 2).count()
+}
+
+/// How many segments cover each point inside `bounds`, anything outside is left out
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Diagram {
+    bounds: Bounds,
+    counts: Vec<u32>,
+}
+
+impl Diagram {
+    fn new(segments: &[LineSegment], bounds: Bounds) -> Diagram {
+        let cells = usize::try_from(bounds.cells()).expect("diagram fits in memory");
+        let width = bounds.width() as usize;
+        let mut counts = vec![0; cells];
+
+        for point in segments
+            .iter()
+            .flat_map(|segment| segment.points_within(&bounds))
+        {
+            let x = (point.x - bounds.min.x) as usize;
+            let y = (point.y - bounds.min.y) as usize;
+            counts[y * width + x] += 1;
+        }
+
+        Diagram { bounds, counts }
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[u32]> {
+        self.counts.chunks(self.bounds.width() as usize)
+    }
+
+    /// The diagram from the puzzle, `.` where nothing passes and the count everywhere else.
+    /// Counts above 9 are drawn as `#`.
+    fn render(&self) -> String {
+        let mut out = String::new();
+
+        for row in self.rows() {
+            let row: String = row
+                .iter()
+                .map(|&count| match count {
+                    0 => '.',
+                    1..=9 => char::from_digit(count, 10).expect("single digit"),
+                    _ => '#',
+                })
+                .collect();
+            writeln!(out, "{}", row).expect("can write");
+        }
+
+        out
+    }
+
+    /// Black where nothing passes, getting brighter with the count up to white for the busiest
+    /// point
+    fn to_pgm(&self) -> String {
+        let mut out = String::new();
+        let busiest = self.counts.iter().cloned().max().unwrap_or(0).max(1);
+        writeln!(
+            out,
+            "P2\n{} {}\n255",
+            self.bounds.width(),
+            self.bounds.height()
+        )
+        .expect("can write");
+
+        for row in self.rows() {
+            let row: Vec<String> = row
+                .iter()
+                .map(|&count| (count * 255 / busiest).to_string())
+                .collect();
+            writeln!(out, "{}", row.join(" ")).expect("can write");
+        }
+
+        out
     }
 }
 
@@ -120,16 +596,8 @@ fn load_file(file_name: &str) -> Vec<LineSegment> {
 
     file.lines()
         .map(|line| {
-            line.split(" -> ").map(|point| {
-                let mut components = point
-                    .split(",")
-                    .map(|num| i64::from_str(num).expect("valid number"));
-
-                let x = components.next().expect("has an x");
-                let y = components.next().expect("has a y");
-
-                Point { x, y }
-            })
+            line.split(" -> ")
+                .map(|point| Point::from_str(point).expect("valid point"))
         })
         .map(|mut points| {
             let start = points.next().expect("has a start");
@@ -144,12 +612,247 @@ fn load_file(file_name: &str) -> Vec<LineSegment> {
 mod tests {
     use super::*;
 
+    fn segment((x1, y1): (i64, i64), (x2, y2): (i64, i64)) -> LineSegment {
+        LineSegment {
+            start: Point { x: x1, y: y1 },
+            end: Point { x: x2, y: y2 },
+        }
+    }
+
+    fn count_overlaps_by_visiting_every_point(segments: &[LineSegment]) -> usize {
+        let mut point_counts = HashMap::new();
+
+        for point in segments.iter().flat_map(|segment| segment.points()) {
+            *point_counts.entry(point).or_insert(0) += 1;
+        }
+
+        point_counts.into_iter().filter(|&(_k, v)| v > 1).count()
+    }
+
     #[test]
-    fn diagonal_line_yields_correct_points() {
-        let line = LineSegment {
-            start: Point { x: 1, y: 1 },
-            end: Point { x: 3, y: 3 },
+    fn example_from_advent_of_code() {
+        let segments = load_file("./src/inputs/day5-example.txt");
+        let straight: Vec<LineSegment> = segments
+            .iter()
+            .filter(|segment| matches!(segment.direction(), Vertical | Horizontal))
+            .cloned()
+            .collect();
+
+        assert_eq!(count_overlaps(&straight), 5);
+        assert_eq!(count_overlaps(&segments), 12);
+    }
+
+    #[test]
+    fn test_count_overlaps_matches_visiting_every_point() {
+        let segments = load_file("./src/inputs/day5.txt");
+
+        assert_eq!(
+            count_overlaps(&segments),
+            count_overlaps_by_visiting_every_point(&segments)
+        );
+    }
+
+    #[test]
+    fn test_huge_segments() {
+        let far = 1_000_000_000_000;
+
+        let segments = [
+            segment((0, 0), (far, 0)),
+            segment((far / 2, 0), (far * 2, 0)),
+            // Crosses inside the overlap, so it doesn't add anything
+            segment((far / 2 + 1, -5), (far / 2 + 1, 5)),
+            // Crosses the single covered stretch of the first segment
+            segment((7, -far), (7, far)),
+            segment((-far, -far), (far, far)),
+        ];
+
+        // (far / 2)..=far on the x axis, plus (7, 0), plus the diagonal meeting the vertical at
+        // (7, 7) and the x axis at the origin
+        assert_eq!(count_overlaps(&segments), (far / 2 + 1) as usize + 3);
+    }
+
+    #[test]
+    fn test_many_lines_through_one_point() {
+        // Overlapping pairs on three of the lines, and a single segment on the fourth, all
+        // through (5, 5) where it should only be counted once
+        let segments = [
+            segment((0, 5), (9, 5)),
+            segment((3, 5), (6, 5)),
+            segment((5, 0), (5, 9)),
+            segment((5, 4), (5, 7)),
+            segment((1, 1), (9, 9)),
+            segment((9, 9), (4, 4)),
+            segment((1, 9), (9, 1)),
+            segment((5, 5), (5, 5)),
+        ];
+
+        assert_eq!(count_overlaps(&segments), 4 + 4 + 6 - 2);
+        assert_eq!(
+            count_overlaps(&segments),
+            count_overlaps_by_visiting_every_point(&segments)
+        );
+    }
+
+    #[test]
+    fn test_single_point_segments() {
+        let segments = [
+            segment((3, 3), (3, 3)),
+            segment((0, 0), (5, 5)),
+            segment((2, 9), (2, 9)),
+            segment((2, 0), (2, 8)),
+            segment((6, 0), (6, 0)),
+            segment((0, 0), (9, 0)),
+        ];
+
+        assert_eq!(count_overlaps(&segments), 5);
+        assert_eq!(
+            count_overlaps(&segments),
+            count_overlaps_by_visiting_every_point(&segments)
+        );
+    }
+
+    #[test]
+    fn test_sloped_segments() {
+        let line = segment((6, 3), (0, 0));
+
+        assert_eq!(line.direction(), Sloped);
+        let expected = [(0, 0), (2, 1), (4, 2), (6, 3)]
+            .iter()
+            .map(|&(x, y)| Point { x, y });
+        assert!(line.points().eq(expected));
+
+        // No lattice points between the ends
+        let line = segment((0, 0), (3, 2));
+        assert_eq!(line.points().count(), 2);
+
+        let segments = [
+            segment((0, 0), (6, 3)),
+            // Passes (3, 1.5) without touching a lattice point, then overlaps from (4, 2)
+            segment((3, 0), (3, 3)),
+            segment((8, 4), (2, 1)),
+            segment((0, 3), (3, 1)),
+        ];
+
+        // (2, 1), (4, 2) and (6, 3) overlap, and the last segment crosses the vertical at (3, 1)
+        assert_eq!(count_overlaps(&segments), 4);
+        assert_eq!(count_overlaps_by_visiting_every_point(&segments), 4);
+        assert_eq!(count_overlaps_with(&segments, Backend::Dense), 4);
+    }
+
+    #[test]
+    fn test_backends_agree() {
+        let segments = load_file("./src/inputs/day5.txt");
+
+        let analytic = count_overlaps_with(&segments, Backend::Analytic);
+        assert_eq!(count_overlaps_with(&segments, Backend::Dense), analytic);
+        assert_eq!(count_overlaps_with(&segments, Backend::Auto), analytic);
+        assert_eq!(count_overlaps_with(&[], Backend::Dense), 0);
+
+        let example = load_file("./src/inputs/day5-example.txt");
+        let bounds = Bounds::of(&example).expect("has segments");
+        assert_eq!(
+            (bounds.min, bounds.max),
+            (Point { x: 0, y: 0 }, Point { x: 9, y: 9 })
+        );
+        assert_eq!(bounds.cells(), 100);
+    }
+
+    #[test]
+    fn test_render_example_diagrams() {
+        let segments = load_file("./src/inputs/day5-example.txt");
+        let bounds = Bounds::of(&segments).expect("has segments");
+        let straight: Vec<LineSegment> = segments
+            .iter()
+            .filter(|segment| matches!(segment.direction(), Vertical | Horizontal))
+            .cloned()
+            .collect();
+
+        assert_eq!(
+            Diagram::new(&straight, bounds).render(),
+            ".......1..\n\
+             ..1....1..\n\
+             ..1....1..\n\
+             .......1..\n\
+             .112111211\n\
+             ..........\n\
+             ..........\n\
+             ..........\n\
+             ..........\n\
+             222111....\n"
+        );
+
+        assert_eq!(
+            Diagram::new(&segments, bounds).render(),
+            "1.1....11.\n\
+             .111...2..\n\
+             ..2.1.111.\n\
+             ...1.2.2..\n\
+             .112313211\n\
+             ...1.2....\n\
+             ..1...1...\n\
+             .1.....1..\n\
+             1.......1.\n\
+             222111....\n"
+        );
+    }
+
+    #[test]
+    fn test_diagram_bounds_and_pgm() {
+        let segments = load_file("./src/inputs/day5-example.txt");
+
+        // Just the middle of the full diagram
+        let bounds = Bounds {
+            min: Point { x: 3, y: 3 },
+            max: Point { x: 6, y: 4 },
         };
+        let diagram = Diagram::new(&segments, bounds);
+        assert_eq!(diagram.render(), "1.2.\n2313\n");
+
+        let pgm = diagram.to_pgm();
+        let lines: Vec<&str> = pgm.lines().collect();
+        assert_eq!(
+            lines,
+            vec!["P2", "4 2", "255", "85 0 170 0", "170 255 85 255"]
+        );
+    }
+
+    #[test]
+    fn test_diagram_clips_long_segments() {
+        let segments = [
+            segment(
+                (1_000_000_000, 1_000_000_000),
+                (-1_000_000_000, -1_000_000_000),
+            ),
+            segment((0, 1_000_000_000), (0, -1_000_000_000)),
+            // Passes through (-3, 3), (0, 2) and (3, 1) inside the box
+            segment(
+                (-3_000_000_003, 1_000_000_003),
+                (3_000_000_003, -999_999_999),
+            ),
+        ];
+        let bounds = Bounds {
+            min: Point { x: -3, y: -1 },
+            max: Point { x: 3, y: 3 },
+        };
+
+        assert_eq!(
+            Diagram::new(&segments, bounds).render(),
+            "..11...\n\
+             ...2...\n\
+             ...11.1\n\
+             ...2.1.\n\
+             1..1..1\n"
+        );
+        assert!(segments[2]
+            .points_within(&bounds)
+            .eq([(-3, 3), (0, 2), (3, 1)]
+                .iter()
+                .map(|&(x, y)| Point { x, y })));
+    }
+
+    #[test]
+    fn diagonal_line_yields_correct_points() {
+        let line = segment((1, 1), (3, 3));
 
         assert_eq!(line.direction(), UpDiagonal);
         let expected = [(1, 1), (2, 2), (3, 3)]
@@ -157,10 +860,7 @@ mod tests {
             .map(|&(x, y)| Point { x, y });
         assert!(line.points().eq(expected));
 
-        let line = LineSegment {
-            start: Point { x: 3, y: 3 },
-            end: Point { x: 1, y: 1 },
-        };
+        let line = segment((3, 3), (1, 1));
 
         assert_eq!(line.direction(), UpDiagonal);
         let expected = [(1, 1), (2, 2), (3, 3)]
@@ -168,10 +868,7 @@ mod tests {
             .map(|&(x, y)| Point { x, y });
         assert!(line.points().eq(expected));
 
-        let line = LineSegment {
-            start: Point { x: 9, y: 7 },
-            end: Point { x: 7, y: 9 },
-        };
+        let line = segment((9, 7), (7, 9));
 
         assert_eq!(line.direction(), DownDiagonal);
         let expected = [(7, 9), (8, 8), (9, 7)]
@@ -180,10 +877,7 @@ mod tests {
             .collect::<Vec<_>>();
         assert_eq!(line.points().collect::<Vec<_>>(), expected);
 
-        let line = LineSegment {
-            start: Point { x: 7, y: 9 },
-            end: Point { x: 9, y: 7 },
-        };
+        let line = segment((7, 9), (9, 7));
 
         assert_eq!(line.direction(), DownDiagonal);
         let expected = [(7, 9), (8, 8), (9, 7)]
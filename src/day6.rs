@@ -1,9 +1,49 @@
 use std::fs::read_to_string;
 use std::str::FromStr;
 
+// Pass a number of days, and optionally a modulus, to project the population that far ahead,
+// e.g. `day6 1000000000000000000 1000000007`. Other species can be projected instead of the
+// lanternfish by passing each lifecycle as `reset,newborn,offspring`, all starting from the
+// puzzle input, e.g. `day6 80 6,8,1 9,11,2`
 fn main() {
     let inputs = load_file("./src/inputs/day6.txt");
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(days) = args.first() {
+        let days = u64::from_str(days).expect("days is a number");
+        let mut counting = Counting::Exact;
+        let mut species = Vec::new();
+        for arg in &args[1..] {
+            if !arg.contains(',') {
+                counting = Counting::Modulo(u64::from_str(arg).expect("modulus is a number"));
+                continue;
+            }
+
+            let numbers: Vec<usize> = arg
+                .split(",")
+                .map(|num| usize::from_str(num).expect("valid number"))
+                .collect();
+            let lifecycle = match numbers[..] {
+                [reset_timer, newborn_timer, offspring] => Lifecycle {
+                    reset_timer,
+                    newborn_timer,
+                    offspring,
+                },
+                _ => panic!("lifecycle {:?} is not reset,newborn,offspring", arg),
+            };
+            species.push(FishCounts::new(lifecycle, inputs.clone()));
+        }
+        if species.is_empty() {
+            species.push(FishCounts::from_iter(inputs));
+        }
+
+        match Population(species).total_after(days, counting) {
+            Some(total) => println!("{:?}", total),
+            None => println!("too many fish to count exactly, pass a modulus"),
+        }
+        return;
+    }
+
     // Part 1
 
     let mut fish_counts = FishCounts::from_iter(inputs.clone());
@@ -17,36 +57,217 @@ fn main() {
     println!("Day 6 pt 2 answer: {:?}", fish_counts.total());
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct FishCounts([usize; 9]);
+/// How a species of fish reproduces. Timers count down to 0, and the day after that a fish goes
+/// back to `reset_timer` and `offspring` new fish start at `newborn_timer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Lifecycle {
+    reset_timer: usize,
+    newborn_timer: usize,
+    offspring: usize,
+}
+
+impl Lifecycle {
+    /// A new fish every 7 days, newborns take 2 extra days for their first
+    const LANTERNFISH: Lifecycle = Lifecycle {
+        reset_timer: 6,
+        newborn_timer: 8,
+        offspring: 1,
+    };
+
+    /// How many timer values a fish can have
+    fn timers(&self) -> usize {
+        self.reset_timer.max(self.newborn_timer) + 1
+    }
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Lifecycle::LANTERNFISH
+    }
+}
+
+/// How many fish of a species have each timer value
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FishCounts {
+    lifecycle: Lifecycle,
+    counts: Vec<usize>,
+}
 
 impl FishCounts {
+    fn new(lifecycle: Lifecycle, timers: impl IntoIterator<Item = usize>) -> Self {
+        let mut counts = vec![0; lifecycle.timers()];
+
+        for timer in timers {
+            assert!(timer < counts.len(), "timer {} is too long", timer);
+            counts[timer] += 1;
+        }
+
+        FishCounts { lifecycle, counts }
+    }
+
     fn total(&self) -> usize {
-        self.0.iter().sum()
+        self.counts.iter().sum()
     }
 
+    /// One day at a time, what `project` is checked against
+    #[cfg(test)]
     fn advance_once(&mut self) {
-        let [day0, day1, day2, day3, day4, day5, day6, day7, day8] = self.0;
+        let spawning = self.counts[0];
 
-        self.0 = [day1, day2, day3, day4, day5, day6, day0 + day7, day8, day0];
+        self.counts.rotate_left(1);
+        *self.counts.last_mut().expect("has timers") = 0;
+        self.counts[self.lifecycle.reset_timer] += spawning;
+        self.counts[self.lifecycle.newborn_timer] += spawning * self.lifecycle.offspring;
     }
 
+    /// Jumps ahead in O(log times) steps, panics if the population no longer fits in a `usize`
     fn advance(&mut self, times: usize) {
-        for _ in 0..times {
-            self.advance_once()
+        let counts = self
+            .project(times as u64, Counting::Exact)
+            .expect("population fits in a u128");
+
+        for (count, projected) in self.counts.iter_mut().zip(counts) {
+            *count = usize::try_from(projected).expect("population fits in a usize");
         }
     }
+
+    /// The counts `days` from now, by raising the one day transition to the power of `days`
+    fn project(&self, days: u64, counting: Counting) -> Option<Vec<u128>> {
+        let transition = Matrix::transition(self.lifecycle, counting)?;
+        let matrix = transition.pow(days, counting)?;
+
+        let mut counts = vec![0; self.counts.len()];
+        for (timer, row) in matrix.0.iter().enumerate() {
+            for (&factor, &fish) in row.iter().zip(self.counts.iter()) {
+                counts[timer] = counting.add(counts[timer], counting.mul(factor, fish as u128)?)?;
+            }
+        }
+
+        Some(counts)
+    }
+
+    fn total_after(&self, days: u64, counting: Counting) -> Option<u128> {
+        self.project(days, counting)?
+            .into_iter()
+            .try_fold(0, |total, count| counting.add(total, count))
+    }
 }
 
-impl FromIterator<usize> for FishCounts {
-    fn from_iter<T: IntoIterator<Item = usize>>(t: T) -> Self {
-        let mut fish_counts = FishCounts([0; 9]);
+/// Several species living side by side, they don't affect each other
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Population(Vec<FishCounts>);
+
+impl Population {
+    fn total_after(&self, days: u64, counting: Counting) -> Option<u128> {
+        self.0.iter().try_fold(0, |total, species| {
+            counting.add(total, species.total_after(days, counting)?)
+        })
+    }
+}
+
+/// How projected counts are kept, exact counts outgrow a `u128` after around 1000 days
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Counting {
+    /// Gives up with `None` rather than overflowing
+    Exact,
+    /// Counts are only known modulo this, which can't overflow since it fits in a `u64`
+    Modulo(u64),
+}
+
+impl Counting {
+    fn reduce(self, value: u128) -> u128 {
+        match self {
+            Counting::Exact => value,
+            Counting::Modulo(modulus) => value % modulus as u128,
+        }
+    }
+
+    fn add(self, a: u128, b: u128) -> Option<u128> {
+        a.checked_add(b).map(|sum| self.reduce(sum))
+    }
+
+    fn mul(self, a: u128, b: u128) -> Option<u128> {
+        self.reduce(a)
+            .checked_mul(self.reduce(b))
+            .map(|product| self.reduce(product))
+    }
+}
+
+/// Entry `[to][from]` is how many fish with timer `to` a single fish with timer `from` turns
+/// into
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Matrix(Vec<Vec<u128>>);
+
+impl Matrix {
+    fn identity(size: usize) -> Matrix {
+        let mut matrix = vec![vec![0; size]; size];
+        for (idx, row) in matrix.iter_mut().enumerate() {
+            row[idx] = 1;
+        }
+
+        Matrix(matrix)
+    }
+
+    /// The same day as `advance_once`, `None` for a modulus of 0
+    fn transition(lifecycle: Lifecycle, counting: Counting) -> Option<Matrix> {
+        if counting == Counting::Modulo(0) {
+            return None;
+        }
 
-        for fish in t {
-            fish_counts.0[fish] += 1;
+        let size = lifecycle.timers();
+        let mut matrix = vec![vec![0; size]; size];
+        for timer in 1..size {
+            matrix[timer - 1][timer] = counting.reduce(1);
         }
+        matrix[lifecycle.reset_timer][0] = counting.reduce(1);
+        matrix[lifecycle.newborn_timer][0] = counting.add(
+            matrix[lifecycle.newborn_timer][0],
+            lifecycle.offspring as u128,
+        )?;
 
-        fish_counts
+        Some(Matrix(matrix))
+    }
+
+    fn mul(&self, other: &Matrix, counting: Counting) -> Option<Matrix> {
+        let size = self.0.len();
+        let mut product = vec![vec![0; size]; size];
+
+        for (row, product_row) in product.iter_mut().enumerate() {
+            for (col, cell) in product_row.iter_mut().enumerate() {
+                for idx in 0..size {
+                    let term = counting.mul(self.0[row][idx], other.0[idx][col])?;
+                    *cell = counting.add(*cell, term)?;
+                }
+            }
+        }
+
+        Some(Matrix(product))
+    }
+
+    /// Square and multiply
+    fn pow(&self, mut exponent: u64, counting: Counting) -> Option<Matrix> {
+        let mut result = Matrix::identity(self.0.len());
+        let mut base = self.clone();
+
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.mul(&base, counting)?;
+            }
+
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base.mul(&base, counting)?;
+            }
+        }
+
+        Some(result)
+    }
+}
+
+/// Lanternfish, with the puzzle's lifecycle
+impl FromIterator<usize> for FishCounts {
+    fn from_iter<T: IntoIterator<Item = usize>>(t: T) -> Self {
+        FishCounts::new(Lifecycle::default(), t)
     }
 }
 
@@ -110,5 +331,137 @@ mod tests {
 
         counts.advance(80);
         assert_eq!(counts.total(), 5934);
+
+        let mut counts = FishCounts::from_iter(vec![3, 4, 3, 1, 2]);
+        counts.advance(256);
+        assert_eq!(counts.total(), 26984457539);
+    }
+
+    #[test]
+    fn test_projection_matches_stepping() {
+        let mut stepped = FishCounts::from_iter(vec![3, 4, 3, 1, 2]);
+
+        for days in 0..300 {
+            let projected = FishCounts::from_iter(vec![3, 4, 3, 1, 2])
+                .project(days, Counting::Exact)
+                .expect("fits");
+            let expected: Vec<u128> = stepped.counts.iter().map(|&count| count as u128).collect();
+            assert_eq!(projected, expected, "after {} days", days);
+
+            stepped.advance_once();
+        }
+    }
+
+    #[test]
+    fn test_modular_projection() {
+        let fish = FishCounts::from_iter(vec![3, 4, 3, 1, 2]);
+        let modulus = 1_000_000_007;
+
+        let exact = fish.total_after(256, Counting::Exact).expect("fits");
+        assert_eq!(
+            fish.total_after(256, Counting::Modulo(modulus)),
+            Some(exact % modulus as u128)
+        );
+
+        // Far too many to count exactly
+        assert_eq!(fish.total_after(1_000_000, Counting::Exact), None);
+
+        // Splitting the days up gives the same answer as jumping straight there
+        let days = 1_000_000_000_000_000_000;
+        let halfway = fish
+            .project(days / 2, Counting::Modulo(modulus))
+            .expect("can't overflow");
+        let mut total = 0;
+        for (timer, &count) in halfway.iter().enumerate() {
+            let from_timer = FishCounts::from_iter([timer])
+                .total_after(days / 2, Counting::Modulo(modulus))
+                .expect("can't overflow");
+            total = (total + count * from_timer) % modulus as u128;
+        }
+        assert_eq!(
+            fish.total_after(days, Counting::Modulo(modulus)),
+            Some(total)
+        );
+
+        assert_eq!(fish.total_after(10, Counting::Modulo(1)), Some(0));
+    }
+
+    #[test]
+    fn test_default_lifecycle_matches_puzzle() {
+        let fish = FishCounts::from_iter(vec![3, 4, 3, 1, 2]);
+        assert_eq!(fish.lifecycle, Lifecycle::LANTERNFISH);
+        assert_eq!(fish.counts.len(), 9);
+        assert_eq!(
+            fish,
+            FishCounts::new(Lifecycle::default(), vec![3, 4, 3, 1, 2])
+        );
+    }
+
+    #[test]
+    fn test_other_lifecycles() {
+        // Twins every 3 days, newborns take 1 extra day
+        let lifecycle = Lifecycle {
+            reset_timer: 2,
+            newborn_timer: 3,
+            offspring: 2,
+        };
+        let mut fish = FishCounts::new(lifecycle, vec![0]);
+
+        fish.advance_once();
+        assert_eq!(fish.counts, vec![0, 0, 1, 2]);
+        fish.advance_once();
+        fish.advance_once();
+        assert_eq!(fish.counts, vec![1, 2, 0, 0]);
+        fish.advance_once();
+        assert_eq!(fish.counts, vec![2, 0, 1, 2]);
+        fish.advance_once();
+        assert_eq!(fish.counts, vec![0, 1, 4, 4]);
+
+        // Newborns can start on the same timer parents reset to
+        let mut fish = FishCounts::new(
+            Lifecycle {
+                reset_timer: 1,
+                newborn_timer: 1,
+                offspring: 1,
+            },
+            vec![0],
+        );
+        fish.advance_once();
+        assert_eq!(fish.counts, vec![0, 2]);
+
+        // Projecting agrees with stepping for any lifecycle
+        let mut stepped = FishCounts::new(lifecycle, vec![0, 3, 1]);
+        let start = stepped.clone();
+        for days in 0..40 {
+            assert_eq!(
+                start.total_after(days, Counting::Exact),
+                Some(stepped.total() as u128)
+            );
+            stepped.advance_once();
+        }
+    }
+
+    #[test]
+    fn test_multiple_species() {
+        let lanternfish = FishCounts::from_iter(vec![3, 4, 3, 1, 2]);
+        let slow = FishCounts::new(
+            Lifecycle {
+                reset_timer: 9,
+                newborn_timer: 11,
+                offspring: 1,
+            },
+            vec![5],
+        );
+        let population = Population(vec![lanternfish, slow.clone()]);
+
+        assert_eq!(
+            population.total_after(80, Counting::Exact),
+            Some(5934 + slow.total_after(80, Counting::Exact).expect("fits"))
+        );
+
+        assert_eq!(
+            population.total_after(18, Counting::Exact),
+            Some(26 + slow.total_after(18, Counting::Exact).expect("fits"))
+        );
     }
 }
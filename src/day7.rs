@@ -1,61 +1,175 @@
 #![feature(int_abs_diff)]
 
-// Nice little 2000x speed up in release mode
-//
-// ➜  advent_of_code_2021 git:(master) ✗ time ./target/debug/day7
-// Day 7 Pt 1 answer: 342641
-// Day 7 Pt 2 answer: 93006301
-// ./target/debug/day7  23.38s user 0.08s system 99% cpu 23.458 total
-//
-// ➜  advent_of_code_2021 git:(master) ✗ time ./target/release/day7
-// Day 7 Pt 1 answer: 342641
-// Day 7 Pt 2 answer: 93006301
-// ./target/release/day7  0.01s user 0.00s system 88% cpu 0.015 total
-
+use std::fmt::Write;
 use std::fs::read_to_string;
 use std::str::FromStr;
 
 // https://adventofcode.com/2021/day/7
+//
+// Pass `costs` to print where each cost model puts the best position, and write every model's
+// cost curve to ./output/day7-costs.csv
 fn main() {
     let inputs = load_file("./src/inputs/day7.txt");
 
+    match std::env::args().nth(1).as_deref() {
+        Some("costs") => {
+            // How the best position moves between cost models
+            let models = CostModel::LIBRARY;
+            for model in models {
+                let curve = cost_curve(&inputs, None, model);
+                let best = best_on_curve(&curve);
+                println!(
+                    "{}: best position {} costs {}",
+                    model.name(),
+                    best,
+                    curve[best]
+                );
+            }
+
+            std::fs::create_dir_all("./output").expect("can create output dir");
+            std::fs::write(
+                "./output/day7-costs.csv",
+                curves_csv(&inputs, None, &models),
+            )
+            .expect("can write costs");
+            return;
+        }
+        Some(other) => panic!("unknown argument {:?}", other),
+        None => {}
+    }
+
     // Part 1
     let pos = best_position_with_constant_cost(&inputs);
-    let gas_used: usize = inputs.iter().map(|num| num.abs_diff(pos)).sum();
+    let gas_used = total_cost(&inputs, pos, |x| x);
     println!("Day 7 Pt 1 answer: {:?}", gas_used);
 
     // Part 2
     let pos = best_position_with_increasing_costs(&inputs);
-    let gas_used: usize = inputs
-        .iter()
-        .map(|num| cost_with_increasing_costs(num.abs_diff(pos)))
-        .sum();
+    let gas_used = total_cost(&inputs, pos, cost_with_increasing_costs);
     println!("Day 7 Pt 2 answer: {:?}", gas_used);
 }
 
+/// The median, moving away from it in either direction passes more crabs than it gets closer to.
+/// With an even number of crabs everything between the middle two costs the same, and the lower
+/// one is picked, the same as trying every position would.
 fn best_position_with_constant_cost(inputs: &[usize]) -> usize {
-    best_position(inputs, |x| x)
+    let mut sorted = inputs.to_vec();
+    let middle = (sorted.len() - 1) / 2;
+
+    *sorted.select_nth_unstable(middle).1
 }
 
+/// The total cost is convex and its real valued minimum is always within 1/2 of the mean, so only
+/// the whole numbers either side of the mean need checking
 fn best_position_with_increasing_costs(inputs: &[usize]) -> usize {
-    best_position(inputs, cost_with_increasing_costs)
+    assert!(!inputs.is_empty(), "at least one input");
+    let sum: usize = inputs.iter().sum();
+    let below = sum / inputs.len();
+
+    (below.saturating_sub(1)..=below + 1)
+        .min_by_key(|&pos| total_cost(inputs, pos, cost_with_increasing_costs))
+        .expect("at least one candidate")
+}
+
+fn total_cost(inputs: &[usize], pos: usize, cost: impl Fn(usize) -> usize) -> usize {
+    inputs.iter().map(|num| cost(num.abs_diff(pos))).sum()
+}
+
+/// 1 + 2 + ... + distance
+fn cost_with_increasing_costs(distance: usize) -> usize {
+    distance * (distance + 1) / 2
+}
+
+/// What moving a single crab a distance costs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CostModel {
+    /// 1 fuel per step, part 1
+    Linear,
+    /// Each step costs 1 more than the one before, part 2
+    Triangular,
+    Quadratic,
+    /// Linear up to the cap, every step after that is free
+    Capped(usize),
 }
 
-fn best_position(inputs: &[usize], cost: impl Fn(usize) -> usize) -> usize {
+impl CostModel {
+    const LIBRARY: [CostModel; 4] = [
+        CostModel::Linear,
+        CostModel::Triangular,
+        CostModel::Quadratic,
+        CostModel::Capped(100),
+    ];
+
+    fn cost(self, distance: usize) -> usize {
+        match self {
+            CostModel::Linear => distance,
+            CostModel::Triangular => cost_with_increasing_costs(distance),
+            CostModel::Quadratic => distance * distance,
+            CostModel::Capped(cap) => distance.min(cap),
+        }
+    }
+
+    fn name(self) -> String {
+        match self {
+            CostModel::Linear => "linear".to_string(),
+            CostModel::Triangular => "triangular".to_string(),
+            CostModel::Quadratic => "quadratic".to_string(),
+            CostModel::Capped(cap) => format!("capped {}", cap),
+        }
+    }
+}
+
+/// The total cost of aligning on every position from 0 to the furthest crab. Each crab's cost is
+/// multiplied by its weight, or counted once without weights.
+fn cost_curve(inputs: &[usize], weights: Option<&[usize]>, model: CostModel) -> Vec<usize> {
+    if let Some(weights) = weights {
+        assert_eq!(weights.len(), inputs.len(), "a weight for every crab");
+    }
     let max_pos = inputs.iter().max().expect("at least one input");
 
     (0..=*max_pos)
-        .min_by_key(|pos| {
+        .map(|pos| {
             inputs
                 .iter()
-                .map(|num| cost(num.abs_diff(*pos)))
-                .sum::<usize>()
+                .enumerate()
+                .map(|(idx, num)| {
+                    let weight = weights.map(|weights| weights[idx]).unwrap_or(1);
+                    weight * model.cost(num.abs_diff(pos))
+                })
+                .sum()
         })
-        .expect("at least one input")
+        .collect()
 }
 
-fn cost_with_increasing_costs(distance: usize) -> usize {
-    (1..=distance).sum()
+/// The first position with the lowest cost
+fn best_on_curve(curve: &[usize]) -> usize {
+    (0..curve.len())
+        .min_by_key(|&pos| curve[pos])
+        .expect("at least one position")
+}
+
+/// One row per position with a column for each model
+fn curves_csv(inputs: &[usize], weights: Option<&[usize]>, models: &[CostModel]) -> String {
+    let curves: Vec<Vec<usize>> = models
+        .iter()
+        .map(|&model| cost_curve(inputs, weights, model))
+        .collect();
+
+    let mut csv = String::from("position");
+    for model in models {
+        write!(csv, ",{}", model.name()).expect("can write");
+    }
+    writeln!(csv).expect("can write");
+
+    for pos in 0..curves.first().map(|curve| curve.len()).unwrap_or(0) {
+        write!(csv, "{}", pos).expect("can write");
+        for curve in curves.iter() {
+            write!(csv, ",{}", curve[pos]).expect("can write");
+        }
+        writeln!(csv).expect("can write");
+    }
+
+    csv
 }
 
 fn load_file(file_name: &str) -> Vec<usize> {
@@ -73,10 +187,115 @@ fn load_file(file_name: &str) -> Vec<usize> {
 mod tests {
     use super::*;
 
+    /// Tries every position, for any cost that grows with distance
+    fn best_position(inputs: &[usize], cost: impl Fn(usize) -> usize) -> usize {
+        let max_pos = inputs.iter().max().expect("at least one input");
+
+        (0..=*max_pos)
+            .min_by_key(|&pos| total_cost(inputs, pos, &cost))
+            .expect("at least one input")
+    }
+
     #[test]
     fn test_best_position() {
         let inputs = [16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
         assert_eq!(best_position_with_constant_cost(&inputs), 2);
         assert_eq!(best_position_with_increasing_costs(&inputs), 5);
+
+        assert_eq!(total_cost(&inputs, 2, |x| x), 37);
+        assert_eq!(total_cost(&inputs, 5, cost_with_increasing_costs), 168);
+    }
+
+    #[test]
+    fn test_cost_models() {
+        let inputs = [16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+
+        let linear = cost_curve(&inputs, None, CostModel::Linear);
+        assert_eq!(linear.len(), 17);
+        assert_eq!((best_on_curve(&linear), linear[2]), (2, 37));
+        assert_eq!(linear[1], 41);
+        assert_eq!(linear[10], 71);
+
+        let triangular = cost_curve(&inputs, None, CostModel::Triangular);
+        assert_eq!((best_on_curve(&triangular), triangular[5]), (5, 168));
+        assert_eq!(triangular[2], 206);
+
+        // Squared distances are smallest around the mean of 4.9
+        let quadratic = cost_curve(&inputs, None, CostModel::Quadratic);
+        assert_eq!(best_on_curve(&quadratic), 5);
+
+        // With everything further than 1 costing the same, the busiest spot wins
+        let capped = cost_curve(&inputs, None, CostModel::Capped(1));
+        assert_eq!(best_on_curve(&capped), 2);
+        assert_eq!(capped[2], 7);
+    }
+
+    #[test]
+    fn test_weights() {
+        let inputs = [0, 10];
+
+        let even = cost_curve(&inputs, Some(&[1, 1]), CostModel::Triangular);
+        assert_eq!(even, cost_curve(&inputs, None, CostModel::Triangular));
+        assert_eq!(best_on_curve(&even), 5);
+
+        // A heavier crab pulls the best position towards itself
+        let heavy = cost_curve(&inputs, Some(&[1, 3]), CostModel::Triangular);
+        assert_eq!(best_on_curve(&heavy), 8);
+        assert_eq!(heavy[8], 36 + 3 * 3);
+    }
+
+    #[test]
+    fn test_curves_csv() {
+        let csv = curves_csv(&[0, 2], None, &[CostModel::Linear, CostModel::Capped(1)]);
+
+        assert_eq!(
+            csv,
+            "position,linear,capped 1\n\
+             0,2,1\n\
+             1,2,2\n\
+             2,2,1\n"
+        );
+    }
+
+    #[test]
+    fn test_matches_trying_every_position() {
+        // A small linear congruential generator so the crabs are spread out unevenly
+        let mut seed: u64 = 7;
+        let mut next = |max: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % max) as usize
+        };
+
+        for len in 1..60 {
+            let inputs: Vec<usize> = (0..len).map(|_| next(50) * next(5)).collect();
+
+            assert_eq!(
+                best_position_with_constant_cost(&inputs),
+                best_position(&inputs, |x| x),
+                "{:?}",
+                inputs
+            );
+            assert_eq!(
+                best_position_with_increasing_costs(&inputs),
+                best_position(&inputs, cost_with_increasing_costs),
+                "{:?}",
+                inputs
+            );
+        }
+    }
+
+    #[test]
+    fn test_millions_of_crabs() {
+        let inputs: Vec<usize> = (0..2_000_000).map(|idx| (idx * 7919) % 100_003).collect();
+
+        let pos = best_position_with_increasing_costs(&inputs);
+        let cost = total_cost(&inputs, pos, cost_with_increasing_costs);
+        assert!(cost <= total_cost(&inputs, pos + 1, cost_with_increasing_costs));
+        assert!(cost <= total_cost(&inputs, pos - 1, cost_with_increasing_costs));
+
+        assert_eq!(cost_with_increasing_costs(0), 0);
+        assert_eq!(cost_with_increasing_costs(11), 66);
     }
 }
@@ -1,8 +1,10 @@
+use std::fmt;
 use std::fs::read_to_string;
+use std::str::FromStr;
 
 fn main() {
     let file = read_to_string("./src/inputs/day8.txt").expect("file exists");
-    let puzzles = parse(&file);
+    let puzzles = parse(&file).expect("valid patterns");
 
     // Part 1
     let occurances_of_1_4_7_8: usize = puzzles
@@ -15,151 +17,405 @@ fn main() {
 
     // Part 2
     //
-    let sum_of_outputs: usize = puzzles.iter().map(|puzzle| puzzle.solve()).sum();
+    let font = Font::standard();
+    let sum_of_outputs: usize = puzzles
+        .iter()
+        .enumerate()
+        .map(|(line, puzzle)| match puzzle.decode(&font) {
+            Ok(decoded) => decoded.value,
+            Err(err) => panic!("line {}: {}", line + 1, err),
+        })
+        .sum();
     println!("Day 8 Pt 2 answer: {:?}", sum_of_outputs)
 }
 
-#[derive(Debug)]
-struct Puzzle<'a> {
-    inputs: Vec<&'a str>,
-    outputs: Vec<&'a str>,
+/// Every display has this many wires and segments, both named from `a`
+const SEGMENTS: usize = 7;
+
+/// A set of wires or segments, bit 0 is `a`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Pattern(u8);
+
+impl Pattern {
+    const ALL: Pattern = Pattern((1 << SEGMENTS) - 1);
+
+    fn single(idx: usize) -> Pattern {
+        Pattern(1 << idx)
+    }
+
+    fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    fn contains(self, idx: usize) -> bool {
+        self.0 & (1 << idx) != 0
+    }
+
+    fn union(self, other: Pattern) -> Pattern {
+        Pattern(self.0 | other.0)
+    }
+
+    fn intersection(self, other: Pattern) -> Pattern {
+        Pattern(self.0 & other.0)
+    }
+
+    fn complement(self) -> Pattern {
+        Pattern(!self.0 & Pattern::ALL.0)
+    }
 }
 
-impl<'a> Puzzle<'a> {
-    fn solve(&self) -> usize {
-        let one = *self
-            .inputs
-            .iter()
-            .find(|input| input.len() == 2)
-            .expect("contains a one");
-        let four = *self
-            .inputs
-            .iter()
-            .find(|input| input.len() == 4)
-            .expect("contains a four");
-        let seven = *self
-            .inputs
-            .iter()
-            .find(|input| input.len() == 3)
-            .expect("contains a seven");
-        let eight = *self
-            .inputs
-            .iter()
-            .find(|input| input.len() == 7)
-            .expect("contains a eight");
-        let zero = find_num(&self.inputs, [(2, one), (3, seven), (3, four), (6, eight)]);
-        let two = find_num(
-            &self.inputs,
-            [(4, zero), (1, one), (2, four), (2, seven), (5, eight)],
-        );
-        let three = find_num(
-            &self.inputs,
-            [
-                (4, zero),
-                (2, one),
-                (4, two),
-                (3, four),
-                (3, seven),
-                (5, eight),
-            ],
-        );
-        let five = find_num(
-            &self.inputs,
-            [
-                (4, zero),
-                (1, one),
-                (3, two),
-                (4, three),
-                (2, seven),
-                (5, eight),
-            ],
-        );
-        let six = find_num(
-            &self.inputs,
-            [
-                (5, zero),
-                (1, one),
-                (4, two),
-                (4, three),
-                (3, four),
-                (5, five),
-            ],
-        );
+impl FromStr for Pattern {
+    type Err = DecodeError;
 
-        let nine = find_num(
-            &self.inputs,
-            [
-                (5, zero),
-                (2, one),
-                (4, two),
-                (5, three),
-                (4, four),
-                (5, five),
-                (5, six),
-                (3, seven),
-                (6, eight),
-            ],
-        );
+    fn from_str(chars: &str) -> Result<Self, Self::Err> {
+        chars
+            .chars()
+            .try_fold(Pattern(0), |pattern, name| match name {
+                'a'..='g' => Ok(pattern.union(Pattern::single(name as usize - 'a' as usize))),
+                other => Err(DecodeError::InvalidWire(other)),
+            })
+    }
+}
 
-        let cases: Vec<(usize, Vec<char>)> =
-            [zero, one, two, three, four, five, six, seven, eight, nine]
-                .iter()
-                .map(|case| {
-                    let mut chars: Vec<char> = case.chars().collect();
-                    chars.sort();
-                    chars
-                })
-                .enumerate()
-                .collect();
-
-        self.outputs
-            .iter()
-            .rev()
-            .map(|&output| {
-                let mut output_chars: Vec<char> = output.chars().collect();
-                output_chars.sort();
-                cases
-                    .iter()
-                    .find(|(_num, case)| case == &output_chars)
-                    .map(|(num, _)| num)
-                    .expect("found one")
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for idx in (0..SEGMENTS).filter(|&idx| self.contains(idx)) {
+            write!(f, "{}", (b'a' + idx as u8) as char)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Which segments light up for each digit, the digit is its position in `glyphs`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Font {
+    glyphs: Vec<Pattern>,
+}
+
+impl Font {
+    fn new<'a>(glyphs: impl IntoIterator<Item = &'a str>) -> Font {
+        Font {
+            glyphs: glyphs
+                .into_iter()
+                .map(|glyph| Pattern::from_str(glyph).expect("valid glyph"))
+                .collect(),
+        }
+    }
+
+    /// The digits from the puzzle text
+    fn standard() -> Font {
+        Font::new([
+            "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+        ])
+    }
+
+    fn digit(&self, segments: Pattern) -> Option<usize> {
+        self.glyphs.iter().position(|&glyph| glyph == segments)
+    }
+}
+
+/// `mapping.0[wire]` is the segment that wire drives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Mapping([usize; SEGMENTS]);
+
+impl Mapping {
+    /// The segments a signal pattern lights up
+    fn translate(&self, pattern: Pattern) -> Pattern {
+        (0..SEGMENTS)
+            .filter(|&wire| pattern.contains(wire))
+            .fold(Pattern(0), |segments, wire| {
+                segments.union(Pattern::single(self.0[wire]))
             })
-            .enumerate()
-            .map(|(idx, value)| value * (10_usize.pow(idx as u32)))
-            .sum()
     }
 }
 
-fn find_num<'a, 'b>(
-    inputs: &[&'a str],
-    cases: impl IntoIterator<Item = (usize, &'b str)>,
-) -> &'a str {
-    let cases: Vec<_> = cases.into_iter().collect();
-    let mut matching: Vec<&str> = inputs
-        .iter()
-        .filter(|input| matches_pattern(input, cases.iter().cloned()))
-        .cloned()
-        .collect();
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DecodeError {
+    InvalidWire(char),
+    /// No way of wiring the display shows every pattern as a digit
+    Inconsistent,
+    /// More than one wiring fits the patterns
+    Ambiguous,
+    /// The output doesn't show a digit with the wiring the patterns settled on
+    UnknownOutput(Pattern),
+}
 
-    assert_eq!(matching.len(), 1);
-    matching.pop().expect("we know there is one")
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidWire(wire) => write!(f, "invalid wire {:?}", wire),
+            DecodeError::Inconsistent => write!(f, "no wiring shows every pattern as a digit"),
+            DecodeError::Ambiguous => write!(f, "more than one wiring fits the patterns"),
+            DecodeError::UnknownOutput(output) => {
+                write!(f, "output {:?} isn't a digit", output.to_string())
+            }
+        }
+    }
 }
 
-fn matches_pattern<'a>(input: &str, cases: impl IntoIterator<Item = (usize, &'a str)>) -> bool {
-    let mut cases = cases.into_iter();
-    cases.all(|(expected_number, pattern)| {
-        input.chars().filter(|c| pattern.contains(*c)).count() == expected_number
-    })
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Decoded {
+    mapping: Mapping,
+    value: usize,
 }
 
-fn parse(file: &str) -> Vec<Puzzle<'_>> {
+#[derive(Debug)]
+struct Puzzle {
+    inputs: Vec<Pattern>,
+    outputs: Vec<Pattern>,
+}
+
+impl Puzzle {
+    fn decode(&self, font: &Font) -> Result<Decoded, DecodeError> {
+        let mapping = find_mapping(&self.inputs, font)?;
+
+        let value = self.outputs.iter().try_fold(0, |value, &output| {
+            let digit = font
+                .digit(mapping.translate(output))
+                .ok_or(DecodeError::UnknownOutput(output))?;
+
+            Ok(value * 10 + digit)
+        })?;
+
+        Ok(Decoded { mapping, value })
+    }
+}
+
+/// Narrow down which segments each wire could drive using the pattern lengths, then search the
+/// remaining wirings for the only one that shows every pattern as a digit
+fn find_mapping(patterns: &[Pattern], font: &Font) -> Result<Mapping, DecodeError> {
+    let mut candidates = [Pattern::ALL; SEGMENTS];
+
+    for &pattern in patterns {
+        // A wire in the pattern drives a segment lit in some glyph of the same length, and a wire
+        // missing from it drives a segment that some glyph of that length leaves dark
+        let (lit, dark) = font
+            .glyphs
+            .iter()
+            .filter(|glyph| glyph.len() == pattern.len())
+            .fold((Pattern(0), Pattern(0)), |(lit, dark), &glyph| {
+                (lit.union(glyph), dark.union(glyph.complement()))
+            });
+
+        for (wire, segments) in candidates.iter_mut().enumerate() {
+            let allowed = if pattern.contains(wire) { lit } else { dark };
+            *segments = segments.intersection(allowed);
+        }
+    }
+
+    let mut found = [None; 2];
+    search(
+        patterns,
+        font,
+        &candidates,
+        &mut [0; SEGMENTS],
+        Pattern(0),
+        &mut found,
+    );
+
+    match found {
+        [None, _] => Err(DecodeError::Inconsistent),
+        [Some(mapping), None] => Ok(mapping),
+        [Some(_), Some(_)] => Err(DecodeError::Ambiguous),
+    }
+}
+
+/// Assigns wires in order to unused candidate segments, stopping once a second wiring is found.
+/// Works in place so decoding doesn't allocate.
+fn search(
+    patterns: &[Pattern],
+    font: &Font,
+    candidates: &[Pattern; SEGMENTS],
+    assigned: &mut [usize; SEGMENTS],
+    used: Pattern,
+    found: &mut [Option<Mapping>; 2],
+) {
+    if found[1].is_some() {
+        return;
+    }
+
+    let wire = used.len();
+    if wire == SEGMENTS {
+        let mapping = Mapping(*assigned);
+        if patterns
+            .iter()
+            .all(|&pattern| font.digit(mapping.translate(pattern)).is_some())
+        {
+            found[found[0].is_some() as usize] = Some(mapping);
+        }
+        return;
+    }
+
+    let open = candidates[wire].intersection(used.complement());
+    for segment in (0..SEGMENTS).filter(|&segment| open.contains(segment)) {
+        assigned[wire] = segment;
+        search(
+            patterns,
+            font,
+            candidates,
+            assigned,
+            used.union(Pattern::single(segment)),
+            found,
+        );
+    }
+}
+
+fn parse(file: &str) -> Result<Vec<Puzzle>, DecodeError> {
+    let patterns = |part: &str| {
+        part.split(" ")
+            .map(Pattern::from_str)
+            .collect::<Result<Vec<_>, _>>()
+    };
+
     file.lines()
         .map(|line| {
             let mut parts = line.split(" | ");
-            let inputs = parts.next().expect("has inputs").split(" ").collect();
-            let outputs = parts.next().expect("has outputs").split(" ").collect();
+            let inputs = patterns(parts.next().expect("has inputs"))?;
+            let outputs = patterns(parts.next().expect("has outputs"))?;
 
-            Puzzle { inputs, outputs }
+            Ok(Puzzle { inputs, outputs })
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    const EXAMPLE: &str =
+        "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf";
+
+    fn patterns(names: &[&str]) -> Vec<Pattern> {
+        names
+            .iter()
+            .map(|name| Pattern::from_str(name).expect("valid pattern"))
+            .collect()
+    }
+
+    #[test]
+    fn example_from_advent_of_code() {
+        let puzzles = parse(EXAMPLE).expect("valid patterns");
+        let decoded = puzzles[0].decode(&Font::standard()).expect("decodes");
+
+        assert_eq!(decoded.value, 5353);
+
+        // The wiring from the puzzle text, d drives the top segment and so on
+        let mapping = decoded.mapping;
+        let segments: Vec<usize> = "deafgbc"
+            .chars()
+            .map(|wire| mapping.0[wire as usize - 'a' as usize])
+            .collect();
+        assert_eq!(segments, vec![0, 1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_patterns() {
+        let pattern = Pattern::from_str("gab").expect("valid pattern");
+        assert_eq!(pattern, Pattern(0b1000011));
+        assert_eq!(pattern.len(), 3);
+        assert_eq!(pattern.to_string(), "abg");
+        assert_eq!(pattern.complement().to_string(), "cdef");
+
+        let other = Pattern::from_str("bcg").expect("valid pattern");
+        assert_eq!(pattern.intersection(other).len(), 2);
+        assert_eq!(pattern.union(other).to_string(), "abcg");
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let font = Font::standard();
+
+        assert_eq!(
+            Pattern::from_str("xbcd"),
+            Err(DecodeError::InvalidWire('x'))
+        );
+        assert!(parse("ab cd | xy").is_err());
+
+        // Two patterns claiming to be a 1
+        let puzzle = Puzzle {
+            inputs: patterns(&["ab", "cd"]),
+            outputs: vec![],
+        };
+        assert_eq!(puzzle.decode(&font), Err(DecodeError::Inconsistent));
+
+        // A 1 on its own doesn't pin down much
+        let puzzle = Puzzle {
+            inputs: patterns(&["ab"]),
+            outputs: vec![],
+        };
+        assert_eq!(puzzle.decode(&font), Err(DecodeError::Ambiguous));
+
+        let puzzle = Puzzle {
+            outputs: patterns(&["a"]),
+            ..parse(EXAMPLE).expect("valid patterns").remove(0)
+        };
+        assert_eq!(
+            puzzle.decode(&font),
+            Err(DecodeError::UnknownOutput(Pattern(1)))
+        );
+    }
+
+    #[test]
+    fn test_other_fonts() {
+        // The same display with the 7 drawn with a bar on the top left, `abcf` rather than `acf`
+        let font = Font::new([
+            "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "abcf", "abcdefg",
+            "abcdfg",
+        ]);
+
+        // The example rewired so the wires are the segments they drive, with the 7 shown
+        let puzzle = Puzzle {
+            inputs: font.glyphs.clone(),
+            outputs: patterns(&["abcf", "cf"]),
+        };
+        let decoded = puzzle.decode(&font).expect("decodes");
+        assert_eq!(decoded.mapping, Mapping([0, 1, 2, 3, 4, 5, 6]));
+        assert_eq!(decoded.value, 71);
+
+        // Four lit segments are now a 4 or a 7, so the standard font rejects the puzzle
+        assert_eq!(
+            puzzle.decode(&Font::standard()),
+            Err(DecodeError::Inconsistent)
+        );
+    }
+
+    #[test]
+    fn test_every_wiring() {
+        let font = Font::standard();
+
+        // All 5040 ways of wiring up a display
+        let wirings: Vec<Vec<usize>> = (0..SEGMENTS).permutations(SEGMENTS).collect();
+        assert_eq!(wirings.len(), 5040);
+
+        for wiring in wirings {
+            // `wiring[segment]` is the wire driving that segment
+            let rewire = |glyph: Pattern| {
+                (0..SEGMENTS)
+                    .filter(|&segment| glyph.contains(segment))
+                    .fold(Pattern(0), |wires, segment| {
+                        wires.union(Pattern::single(wiring[segment]))
+                    })
+            };
+            let puzzle = Puzzle {
+                inputs: font
+                    .glyphs
+                    .iter()
+                    .rev()
+                    .map(|&glyph| rewire(glyph))
+                    .collect(),
+                outputs: [9, 0, 4, 2]
+                    .iter()
+                    .map(|&digit| rewire(font.glyphs[digit]))
+                    .collect(),
+            };
+
+            let decoded = puzzle.decode(&font).expect("decodes");
+            assert_eq!(decoded.value, 9042);
+            for (segment, &wire) in wiring.iter().enumerate() {
+                assert_eq!(decoded.mapping.0[wire], segment);
+            }
+        }
+    }
+}
@@ -1,13 +1,46 @@
 #![allow(dead_code)]
-#![feature(map_first_last)]
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
 use std::fs::read_to_string;
 use std::str::FromStr;
 
+// Pass `render` to print the map with each basin in its own color, or `histogram` to print how
+// many basins there are of each size. Either can be followed by `downhill`, `diagonal` or
+// `walls-from=<height>` to change how basins are found, e.g. `day9 histogram downhill`. The
+// puzzle answers always use the puzzle's own rules
 fn main() {
     let heat_map = load_file("./src/inputs/day9.txt");
 
+    let mut command = None;
+    let mut rules = BasinRules::default();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "render" | "histogram" => command = Some(arg),
+            "downhill" => rules.mode = Mode::Downhill,
+            "diagonal" => rules.connectivity = Connectivity::Eight,
+            other => match other.strip_prefix("walls-from=") {
+                Some(height) => {
+                    rules.wall = Wall::Threshold(isize::from_str(height).expect("valid height"))
+                }
+                None => panic!("unknown argument {:?}", other),
+            },
+        }
+    }
+
+    match command.as_deref() {
+        Some("render") => {
+            print!("{}", heat_map.basins_with(&rules).render(&heat_map));
+            return;
+        }
+        Some("histogram") => {
+            let basins = heat_map.basins_with(&rules);
+            print!("{}", render_histogram(&size_histogram(&basins.basins)));
+            return;
+        }
+        _ => {}
+    }
+
     let low_points: Vec<Position> = heat_map
         .iter_positions()
         // find the ones with all neighbors at lower value
@@ -29,32 +62,8 @@ fn main() {
     println!("Day 9 Pt. 1 answer: {:?}", answer);
 
     // Part 2
-    let mut basin_sizes: Vec<usize> = low_points
-        .iter()
-        .map(|pos| {
-            let mut unchecked: BTreeSet<Position> = BTreeSet::from_iter([*pos]);
-            let mut checked = BTreeSet::new();
-            let mut in_basin = BTreeSet::new();
-
-            while let Some(curr) = unchecked.pop_first() {
-                in_basin.insert(curr);
-                checked.insert(curr);
-
-                for (next, value) in heat_map.neighbors(curr) {
-                    match value {
-                        9 => {}
-                        _ => {
-                            if !checked.contains(&next) {
-                                unchecked.insert(next);
-                            }
-                        }
-                    }
-                }
-            }
-
-            in_basin.len()
-        })
-        .collect();
+    let basins = heat_map.basins();
+    let mut basin_sizes: Vec<usize> = basins.basins.iter().map(|basin| basin.size).collect();
     basin_sizes.sort();
 
     println!(
@@ -77,9 +86,19 @@ impl HeightMap {
         })
     }
 
-    fn neighbors(&self, (x, y): Position) -> impl Iterator<Item = (Position, isize)> + '_ {
-        [(x, y + 1), (x, y - 1), (x + 1, y), (x - 1, y)]
-            .into_iter()
+    fn neighbors(&self, pos: Position) -> impl Iterator<Item = (Position, isize)> + '_ {
+        self.neighbors_with(pos, Connectivity::Four)
+    }
+
+    fn neighbors_with(
+        &self,
+        (x, y): Position,
+        connectivity: Connectivity,
+    ) -> impl Iterator<Item = (Position, isize)> + '_ {
+        connectivity
+            .offsets()
+            .iter()
+            .map(move |(dx, dy)| (x + dx, y + dy))
             .filter_map(|pos| self.at(pos).map(|val| (pos, val)))
     }
 
@@ -100,6 +119,265 @@ impl HeightMap {
     }
 }
 
+/// Which heights separate basins
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Wall {
+    /// Anything this high or higher
+    Threshold(isize),
+    Heights(BTreeSet<isize>),
+}
+
+impl Wall {
+    fn contains(&self, height: isize) -> bool {
+        match self {
+            Wall::Threshold(threshold) => height >= *threshold,
+            Wall::Heights(heights) => heights.contains(&height),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Connectivity {
+    /// Up, down, left and right, like the puzzle
+    Four,
+    /// Diagonals as well
+    Eight,
+}
+
+impl Connectivity {
+    fn offsets(self) -> &'static [Position] {
+        match self {
+            Connectivity::Four => &[(0, 1), (0, -1), (1, 0), (-1, 0)],
+            Connectivity::Eight => &[
+                (0, 1),
+                (0, -1),
+                (1, 0),
+                (-1, 0),
+                (1, 1),
+                (1, -1),
+                (-1, 1),
+                (-1, -1),
+            ],
+        }
+    }
+
+    /// The neighbors a scan in reading order has already been past
+    fn earlier(self) -> &'static [Position] {
+        match self {
+            Connectivity::Four => &[(-1, 0), (0, -1)],
+            Connectivity::Eight => &[(-1, 0), (0, -1), (-1, -1), (1, -1)],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Every cell that can reach another without crossing a wall shares a basin
+    Connected,
+    /// Water runs to the lowest strictly lower neighbor, the first in `Connectivity::offsets`
+    /// order on a tie, and a basin is everything that drains to the same low point. A cell with
+    /// no lower neighbor is a low point, even on a plateau.
+    Downhill,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BasinRules {
+    wall: Wall,
+    connectivity: Connectivity,
+    mode: Mode,
+}
+
+/// The puzzle's basins, split by 9s
+impl Default for BasinRules {
+    fn default() -> Self {
+        BasinRules {
+            wall: Wall::Heights(BTreeSet::from([9])),
+            connectivity: Connectivity::Four,
+            mode: Mode::Connected,
+        }
+    }
+}
+
+/// The cells of one basin, walls are never part of one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Basin {
+    /// The lowest cell, the first one in reading order if there's a tie
+    low_point: Position,
+    size: usize,
+    /// Top left corner of the bounding box
+    min: Position,
+    /// Bottom right corner of the bounding box, inclusive
+    max: Position,
+}
+
+/// Every cell labelled with the basin it's in, walls aren't in any
+struct Basins {
+    width: usize,
+    labels: Vec<Option<usize>>,
+    basins: Vec<Basin>,
+}
+
+impl Basins {
+    fn label(&self, (x, y): Position) -> Option<usize> {
+        self.labels[y as usize * self.width + x as usize]
+    }
+
+    /// The heights with each basin in a color, cycling through 6 so neighbors rarely match.
+    /// Walls are dimmed, and every basin cell turns dimming back off.
+    fn render(&self, map: &HeightMap) -> String {
+        let mut out = String::new();
+
+        for y in 0..map.height() as isize {
+            for x in 0..map.width() as isize {
+                let height = map.at((x, y)).expect("on the map");
+                match self.label((x, y)) {
+                    Some(id) => write!(out, "\x1b[22;{}m{}", 31 + id % 6, height),
+                    None => write!(out, "\x1b[39;2m{}", height),
+                }
+                .expect("can write");
+            }
+            writeln!(out, "\x1b[0m").expect("can write");
+        }
+
+        out
+    }
+}
+
+/// Disjoint sets of cell indexes, roots are their own parent
+fn find(parents: &mut [usize], mut idx: usize) -> usize {
+    while parents[idx] != idx {
+        parents[idx] = parents[parents[idx]];
+        idx = parents[idx];
+    }
+
+    idx
+}
+
+impl HeightMap {
+    fn basins(&self) -> Basins {
+        self.basins_with(&BasinRules::default())
+    }
+
+    /// Labels basins in one scan over the map. Connected cells are joined with the neighbors the
+    /// scan has already been past, and downhill cells with the neighbor they drain into. Basin
+    /// ids are then handed out in reading order.
+    fn basins_with(&self, rules: &BasinRules) -> Basins {
+        let width = self.width();
+        let index = |(x, y): Position| y as usize * width + x as usize;
+        let is_open =
+            |pos: Position| matches!(self.at(pos), Some(height) if !rules.wall.contains(height));
+
+        let mut parents: Vec<usize> = (0..width * self.height()).collect();
+        let mut join = |a: Position, b: Position| {
+            let root = find(&mut parents, index(a));
+            let other = find(&mut parents, index(b));
+            parents[other] = root;
+        };
+
+        for (pos, height) in self.iter_rows() {
+            if !is_open(pos) {
+                continue;
+            }
+
+            match rules.mode {
+                Mode::Connected => {
+                    for (dx, dy) in rules.connectivity.earlier() {
+                        let earlier = (pos.0 + dx, pos.1 + dy);
+                        if is_open(earlier) {
+                            join(earlier, pos);
+                        }
+                    }
+                }
+                Mode::Downhill => {
+                    let lowest = self
+                        .neighbors_with(pos, rules.connectivity)
+                        .filter(|&(next, next_height)| is_open(next) && next_height < height)
+                        .fold(
+                            None,
+                            |lowest: Option<(Position, isize)>, next| match lowest {
+                                Some(lowest) if lowest.1 <= next.1 => Some(lowest),
+                                _ => Some(next),
+                            },
+                        );
+
+                    if let Some((next, _height)) = lowest {
+                        join(next, pos);
+                    }
+                }
+            }
+        }
+
+        let mut ids: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut labels = vec![None; parents.len()];
+        let mut basins: Vec<Basin> = Vec::new();
+
+        for (pos, height) in self.iter_rows() {
+            if !is_open(pos) {
+                continue;
+            }
+
+            let root = find(&mut parents, index(pos));
+            let next_id = ids.len();
+            let id = *ids.entry(root).or_insert(next_id);
+            labels[index(pos)] = Some(id);
+
+            if id == basins.len() {
+                basins.push(Basin {
+                    low_point: pos,
+                    size: 0,
+                    min: pos,
+                    max: pos,
+                });
+            }
+
+            let basin = &mut basins[id];
+            basin.size += 1;
+            if height < self.at(basin.low_point).expect("on the map") {
+                basin.low_point = pos;
+            }
+            basin.min = (basin.min.0.min(pos.0), basin.min.1.min(pos.1));
+            basin.max = (basin.max.0.max(pos.0), basin.max.1.max(pos.1));
+        }
+
+        Basins {
+            width,
+            labels,
+            basins,
+        }
+    }
+
+    /// Like `iter_positions` but a row at a time
+    fn iter_rows(&self) -> impl Iterator<Item = (Position, isize)> + '_ {
+        (0..self.height()).flat_map(move |y| {
+            (0..self.width()).map(move |x| {
+                let pos = (x as isize, y as isize);
+                (pos, self.0[y][x])
+            })
+        })
+    }
+}
+
+/// How many basins there are of each size
+fn size_histogram(basins: &[Basin]) -> BTreeMap<usize, usize> {
+    let mut histogram = BTreeMap::new();
+
+    for basin in basins {
+        *histogram.entry(basin.size).or_insert(0) += 1;
+    }
+
+    histogram
+}
+
+fn render_histogram(histogram: &BTreeMap<usize, usize>) -> String {
+    let mut out = String::new();
+
+    for (size, count) in histogram {
+        writeln!(out, "{:>5} | {} {}", size, "#".repeat(*count), count).expect("can write");
+    }
+
+    out
+}
+
 fn load_file(file_name: &str) -> HeightMap {
     let vals = read_to_string(file_name)
         .expect("valid file")
@@ -114,3 +392,174 @@ fn load_file(file_name: &str) -> HeightMap {
 
     HeightMap(vals)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_from_advent_of_code() {
+        let map = load_file("./src/inputs/day9-example.txt");
+        let basins = map.basins();
+
+        let mut sizes: Vec<usize> = basins.basins.iter().map(|basin| basin.size).collect();
+        assert_eq!(sizes, vec![3, 9, 14, 9]);
+
+        sizes.sort();
+        assert_eq!(sizes.iter().rev().take(3).product::<usize>(), 1134);
+
+        let low_points: Vec<Position> = basins.basins.iter().map(|basin| basin.low_point).collect();
+        assert_eq!(low_points, vec![(1, 0), (9, 0), (2, 2), (6, 4)]);
+    }
+
+    #[test]
+    fn test_labels_and_bounding_boxes() {
+        let map = load_file("./src/inputs/day9-example.txt");
+        let basins = map.basins();
+
+        assert_eq!(basins.label((0, 0)), Some(0));
+        assert_eq!(basins.label((2, 0)), None);
+        assert_eq!(basins.label((9, 2)), Some(1));
+        assert_eq!(basins.label((0, 3)), Some(2));
+        assert_eq!(basins.label((9, 4)), Some(3));
+
+        assert_eq!(
+            basins.basins[1],
+            Basin {
+                low_point: (9, 0),
+                size: 9,
+                min: (5, 0),
+                max: (9, 2),
+            }
+        );
+        assert_eq!(
+            (basins.basins[2].min, basins.basins[2].max),
+            ((0, 1), (5, 4))
+        );
+    }
+
+    #[test]
+    fn test_walls() {
+        let map = load_file("./src/inputs/day9-example.txt");
+        let sizes = |rules: &BasinRules| -> Vec<usize> {
+            map.basins_with(rules)
+                .basins
+                .iter()
+                .map(|basin| basin.size)
+                .collect()
+        };
+
+        // Nothing is higher than 9, so these are the same walls
+        let threshold = BasinRules {
+            wall: Wall::Threshold(9),
+            ..BasinRules::default()
+        };
+        assert_eq!(sizes(&threshold), sizes(&BasinRules::default()));
+
+        // Lower walls take the 8s out of every basin
+        let lower = BasinRules {
+            wall: Wall::Threshold(8),
+            ..BasinRules::default()
+        };
+        assert_eq!(sizes(&lower), vec![3, 9, 7, 6]);
+
+        // With no walls at all everything is one basin
+        let none = BasinRules {
+            wall: Wall::Heights(BTreeSet::new()),
+            ..BasinRules::default()
+        };
+        assert_eq!(sizes(&none), vec![50]);
+    }
+
+    #[test]
+    fn test_diagonals() {
+        let map = load_file("./src/inputs/day9-example.txt");
+        let rules = BasinRules {
+            connectivity: Connectivity::Eight,
+            ..BasinRules::default()
+        };
+        let basins = map.basins_with(&rules);
+
+        // Every basin touches another at a corner, so they all merge
+        let sizes: Vec<usize> = basins.basins.iter().map(|basin| basin.size).collect();
+        assert_eq!(sizes, vec![35]);
+        assert_eq!(basins.basins[0].low_point, (9, 0));
+    }
+
+    #[test]
+    fn test_downhill() {
+        let map = load_file("./src/inputs/day9-example.txt");
+        let rules = BasinRules {
+            mode: Mode::Downhill,
+            ..BasinRules::default()
+        };
+
+        // Each of the puzzle's basins has a single low point, so water ends up in the same place
+        assert_eq!(map.basins_with(&rules).basins, map.basins().basins);
+
+        // Each 3 is a ridge, so one connected region becomes a basin per low point. Both ridges
+        // drain to their lowest neighbor.
+        let map = HeightMap(vec![vec![1, 3, 2, 3, 0]]);
+        let basins = map.basins_with(&rules);
+        let low_points: Vec<(Position, usize)> = basins
+            .basins
+            .iter()
+            .map(|basin| (basin.low_point, basin.size))
+            .collect();
+        assert_eq!(low_points, vec![((0, 0), 2), ((2, 0), 1), ((4, 0), 2)]);
+        assert_eq!(map.basins().basins.len(), 1);
+
+        // Both of the 3's neighbors are equally low, so it drains to the first in `offsets`
+        // order, which is on its right
+        let map = HeightMap(vec![vec![1, 3, 1]]);
+        let basins = map.basins_with(&rules);
+        let middle = basins.label((1, 0)).expect("in a basin");
+        assert_eq!(basins.basins[middle].low_point, (2, 0));
+        assert_eq!(basins.basins[middle].size, 2);
+        assert_eq!(basins.basins.len(), 2);
+    }
+
+    #[test]
+    fn test_render_and_histogram() {
+        let map = load_file("./src/inputs/day9-example.txt");
+        let basins = map.basins();
+
+        // Play the escapes back like a terminal would, keeping track of the color and dimming
+        // each height is drawn with
+        let rendered = basins.render(&map);
+        let mut styles = Vec::new();
+        let (mut color, mut dim) = (None, false);
+        for part in rendered.split("\x1b[").skip(1) {
+            let (codes, text) = part.split_once('m').expect("escape ends in m");
+            for code in codes.split(';') {
+                match code {
+                    "0" => (color, dim) = (None, false),
+                    "2" => dim = true,
+                    "22" => dim = false,
+                    "39" => color = None,
+                    code => color = Some(code.to_string()),
+                }
+            }
+            for _height in text.trim_end().chars() {
+                styles.push((color.clone(), dim));
+            }
+        }
+
+        assert_eq!(styles.len(), 50);
+        for (idx, (color, dim)) in styles.into_iter().enumerate() {
+            let pos = ((idx % 10) as isize, (idx / 10) as isize);
+            match basins.label(pos) {
+                Some(id) => assert_eq!((color, dim), (Some((31 + id % 6).to_string()), false)),
+                None => assert_eq!((color, dim), (None, true)),
+            }
+        }
+        assert_eq!(rendered.lines().count(), 5);
+
+        let histogram = size_histogram(&basins.basins);
+        assert_eq!(histogram, BTreeMap::from([(3, 1), (9, 2), (14, 1)]));
+        assert_eq!(
+            render_histogram(&histogram),
+            "    3 | # 1\n    9 | ## 2\n   14 | # 1\n"
+        );
+    }
+}